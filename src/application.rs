@@ -1,24 +1,39 @@
-use std::env;
-use std::fs::OpenOptions;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
 
 use crate::buffer::Buffer;
-use crate::event_loop::{Event, EventLoop};
-use crate::project::ProjectContainer;
+use crate::crypto::{self, Cipher};
+use crate::event_loop::{Event, EventLoop, modification_time};
+use crate::formats::FileFormat;
+use crate::history;
+use crate::layout::{self, Constraint, Direction};
+use crate::lock::Lock;
+use crate::project::{self, ProjectContainer};
+use crate::state::{InputHistory, InputKind, Panes, State};
+use crate::storage::{self, JsonStorage, Storage};
 use crate::task::TaskContainer;
 use crate::terminal;
 use crate::utils::Rect;
 use crate::utils::build_row;
 use crate::widgets::Widget;
 use crate::widgets::line_input::LineInput;
+use crate::widgets::list_popup::ListPopup;
 use crate::widgets::message_box::MessageBox;
 use crate::widgets::{ContainerWidget, PopupWidget};
+use crate::workspace;
 use crossterm::event::Event as CrosstermEvent;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use crossterm::style::Stylize;
 use crossterm::style::StyledContent;
 
 const MAX_LOG_DURATION: u8 = 3;
+// Percent by which a pane grows or shrinks with every key press
+const PANE_STEP: i16 = 5;
+// Smallest terminal size the application is drawn in
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 10;
+// Longest time between two clicks on the same task that counts as a double click
+const DOUBLE_CLICK: Duration = Duration::from_millis(500);
 
 #[derive(Debug, PartialEq)]
 pub enum InputMode {
@@ -28,14 +43,42 @@ pub enum InputMode {
     Delete,
     Save,
     Quit,
+    Reload,
+    Export,
+    History,
+    Passphrase,
+    Workspaces,
+    NewWorkspace,
 }
 
+// Borders between panes that can be dragged with the mouse
+enum Divider {
+    // Between the project pane and the task pane
+    Projects,
+    // Between the task list and the task content
+    TaskList,
+}
+
+// Buttons of the message box that is shown if the data file was changed on disk
+const RELOAD_BUTTONS: [&str; 3] = ["Merge", "Keep mine", "Take theirs"];
+const RELOAD_MERGE: usize = 0;
+const RELOAD_KEEP_MINE: usize = 1;
+const RELOAD_TAKE_THEIRS: usize = 2;
+
+// Choices when quitting with unsaved modifications
+const QUIT_BUTTONS: [&str; 3] = ["Save", "Discard", "Cancel"];
+const QUIT_SAVE: usize = 0;
+const QUIT_DISCARD: usize = 1;
+const QUIT_CANCEL: usize = 2;
+
 // App holds the state of the application
 pub struct TodoApp {
     // Current input mode
     input_mode: InputMode,
     // Container that will manage and display all projects
     projects: ProjectContainer,
+    // Projects as they are in the data file, the common base when merging external changes
+    base: ProjectContainer,
     // Container that will manage and display all tasks of a project
     tasks: TaskContainer,
     // Bool that stores whether the user wants to quit
@@ -47,64 +90,125 @@ pub struct TodoApp {
     log_message_duration: u8,
     // Whether the current state was modified
     dirty: bool,
+    // Whether the screen has to be rendered again
+    redraw: bool,
+    // Sizes of the panes
+    panes: Panes,
+    // Area of the panes when they were rendered the last time
+    panes_area: Rect,
+    // Divider that is currently dragged with the mouse
+    dragging: Option<Divider>,
+    // Time and task of the last click, used to detect double clicks
+    last_click: Option<(Instant, usize)>,
     // Message box widget
     message_box: MessageBox,
     // Line input wideget
     line_input: LineInput,
+    // Input for the passphrase of an encrypted data file
+    passphrase_input: LineInput,
+    // List of past versions of the data
+    list_popup: ListPopup,
+    // Commit that is viewed and the projects that were replaced to view it
+    history_view: Option<(String, ProjectContainer)>,
+    // Ids of the commits that are listed in the history popup
+    history_commits: Vec<String>,
+    // Names of the workspaces that are listed in the workspace popup
+    workspaces: Vec<String>,
+    // Workspace and its cipher that was open before switching to a locked workspace
+    // Cancelling the passphrase goes back to it
+    previous_workspace: Option<(String, Option<Cipher>)>,
     // Event loop that controls draw and crossterm key events
     event_loop: EventLoop,
+    // Modification time of the data file when it was last loaded or saved
+    data_modified: Option<SystemTime>,
+    // Whether the data file was changed by someone else and the change was not handled yet
+    data_changed: bool,
+    // Lock of the data file, the application is read-only if another instance holds it
+    lock: Option<Lock>,
+    // Storage that projects are loaded from and saved to
+    storage: Box<dyn Storage>,
+    // Cipher of the data file if it is encrypted
+    cipher: Option<Cipher>,
     buffer: Buffer,
 }
 
 impl TodoApp {
-    pub fn new() -> Self {
-        Self {
+    pub fn new(storage: Box<dyn Storage>) -> Self {
+        let lock = Lock::acquire(&Self::lock_path());
+        let mut app = Self {
             input_mode: InputMode::Normal,
             projects: ProjectContainer::new(true),
+            base: ProjectContainer::new(true),
             tasks: TaskContainer::new(false),
             quit: false,
             log_message: String::new(),
             log_message_duration: 0,
             dirty: false,
+            // Draw the first frame right away
+            redraw: true,
+            panes: Panes::default(),
+            panes_area: terminal::size(),
+            dragging: None,
+            last_click: None,
             message_box: MessageBox::new(),
             line_input: LineInput::new(),
-            event_loop: EventLoop::start(),
+            passphrase_input: LineInput::masked("Passphrase"),
+            list_popup: ListPopup::new(),
+            history_view: None,
+            history_commits: Vec::new(),
+            workspaces: Vec::new(),
+            previous_workspace: None,
+            event_loop: EventLoop::start(storage.path().to_path_buf(), None),
+            data_modified: None,
+            data_changed: false,
+            lock: None,
+            storage,
+            cipher: None,
             buffer: Buffer::new(terminal::size()),
+        };
+        // The projects are loaded after the passphrase was entered
+        if !Self::database_path().exists() && crypto::is_encrypted_file(&Self::data_path()) {
+            app.input_mode = InputMode::Passphrase;
         }
+        match lock {
+            Ok(lock) => app.lock = Some(lock),
+            Err(error) => app.log(&format!("{}, opened read-only", error)),
+        }
+        app.panes = State::load().panes;
+        app.tasks.set_list_height(app.panes.task_list);
+
+        app
     }
 
     // Main loop
     pub async fn run(&mut self) -> Result<(), std::io::Error> {
         loop {
-            match self.event_loop.event_rx.recv().await {
-                Some(Event::Draw) => {
-                    if self.log_message_duration > MAX_LOG_DURATION {
-                        self.log_message = String::new();
-                    }
-                    // Increment log duration
-                    if !self.log_message.is_empty() {
-                        self.log_message_duration += 1;
-                    }
-                }
-                Some(Event::Key(CrosstermEvent::Key(key))) => {
-                    self.handle_key_event(&key).await;
+            // Render app in terminal, but only if something changed
+            // This happens before waiting for the next event, so the first frame is shown
+            // right away instead of after the first tick
+            if self.redraw {
+                self.redraw = false;
+                self.render()?;
+            }
 
-                    if self.input_mode == InputMode::Save
-                        || self.input_mode == InputMode::Quit
-                        || self.input_mode == InputMode::Delete
-                    {
-                        self.message_box.process_input(&key);
-                    } else if self.input_mode == InputMode::Insert
-                        || self.input_mode == InputMode::Rename
-                    {
-                        self.line_input.process_input(&key);
-                    }
-                }
-                _ => {}
-            };
+            let event = self.event_loop.event_rx.recv().await;
+            self.handle_event(event).await;
+            // Handle everything that is already queued, e.g. pasted text, before drawing
+            while !self.quit
+                && let Ok(event) = self.event_loop.event_rx.try_recv()
+            {
+                self.handle_event(Some(event)).await;
+            }
 
-            // Render app in terminal
-            self.render()?;
+            // Only handle external changes when the user is not in the middle of something
+            if self.data_changed
+                && self.input_mode == InputMode::Normal
+                && self.history_view.is_none()
+            {
+                self.data_changed = false;
+                self.handle_data_change();
+                self.redraw = true;
+            }
 
             if self.quit {
                 break;
@@ -114,14 +218,101 @@ impl TodoApp {
         Ok(())
     }
 
+    async fn handle_event(&mut self, event: Option<Event>) {
+        match event {
+            Some(Event::Tick) => {
+                if self.log_message_duration > MAX_LOG_DURATION {
+                    self.log_message = String::new();
+                    self.log_message_duration = 0;
+                    self.redraw = true;
+                }
+                // Increment log duration
+                if !self.log_message.is_empty() {
+                    self.log_message_duration += 1;
+                }
+                // Start editing as soon as the other instance releases the lock
+                if self.read_only()
+                    && let Ok(lock) = Lock::acquire(&Self::lock_path())
+                {
+                    self.lock = Some(lock);
+                    self.log("Lock acquired, editing is enabled");
+                }
+            }
+            // Ignore changes that were made by this instance
+            Some(Event::DataChanged)
+                if modification_time(self.storage.path()) != self.data_modified =>
+            {
+                self.data_changed = true;
+            }
+            Some(Event::Terminal(CrosstermEvent::Key(key))) => {
+                self.handle_key_event(&key).await;
+
+                if self.input_mode == InputMode::Save
+                    || self.input_mode == InputMode::Quit
+                    || self.input_mode == InputMode::Delete
+                    || self.input_mode == InputMode::Reload
+                    || self.input_mode == InputMode::Export
+                {
+                    self.message_box.process_input(&key);
+                } else if self.input_mode == InputMode::Insert
+                    || self.input_mode == InputMode::Rename
+                    || self.input_mode == InputMode::NewWorkspace
+                {
+                    self.line_input.process_input(&key);
+                } else if self.input_mode == InputMode::History
+                    || self.input_mode == InputMode::Workspaces
+                {
+                    self.list_popup.process_input(&key);
+                } else if self.input_mode == InputMode::Passphrase {
+                    self.passphrase_input.process_input(&key);
+                }
+                self.redraw = true;
+            }
+            Some(Event::Terminal(CrosstermEvent::Paste(text))) => {
+                if self.input_mode == InputMode::Insert
+                    || self.input_mode == InputMode::Rename
+                    || self.input_mode == InputMode::NewWorkspace
+                {
+                    self.line_input.paste(&text);
+                } else if self.input_mode == InputMode::Passphrase {
+                    self.passphrase_input.paste(&text);
+                }
+                self.redraw = true;
+            }
+            Some(Event::Terminal(CrosstermEvent::Mouse(mouse))) => {
+                self.handle_mouse_event(&mouse).await
+            }
+            // The buffer notices the new size when rendering and draws everything again
+            Some(Event::Terminal(CrosstermEvent::Resize(_, _))) => self.redraw = true,
+            _ => {}
+        }
+    }
+
     fn mode(&self, length: u16) -> StyledContent<String> {
         match self.input_mode {
+            InputMode::Normal if let Some((commit, _)) = &self.history_view => {
+                build_row(vec![(&format!("HISTORY {}", commit), length as usize)])
+                    .black()
+                    .on_blue()
+            }
+            InputMode::Normal if self.read_only() => {
+                build_row(vec![("READ-ONLY", length as usize)]).black().on_grey()
+            }
             InputMode::Normal => build_row(vec![("INPUT", length as usize)]).black().on_cyan(),
             InputMode::Insert => build_row(vec![("INSERT", length as usize)]).black().on_green(),
             InputMode::Rename => build_row(vec![("RENAME", length as usize)]).black().on_red(),
             InputMode::Save => build_row(vec![("SAVE", length as usize)]).black().on_magenta(),
             InputMode::Quit => build_row(vec![("QUIT", length as usize)]).black().on_grey(),
             InputMode::Delete => build_row(vec![("DELETE", length as usize)]).black().on_grey(),
+            InputMode::Reload => build_row(vec![("RELOAD", length as usize)]).black().on_yellow(),
+            InputMode::Export => build_row(vec![("EXPORT", length as usize)]).black().on_grey(),
+            InputMode::History => build_row(vec![("HISTORY", length as usize)]).black().on_blue(),
+            InputMode::Passphrase => {
+                build_row(vec![("LOCKED", length as usize)]).black().on_red()
+            }
+            InputMode::Workspaces | InputMode::NewWorkspace => {
+                build_row(vec![("WORKSPACE", length as usize)]).black().on_blue()
+            }
         }
     }
 
@@ -129,58 +320,140 @@ impl TodoApp {
         // An area where the application will be drawn
         // This is normally the whole teminal size
         let area = terminal::size();
-        self.buffer.resize(&area);
-
-        // Display projects
-        let projects_area = Rect {
-            x: area.x,
-            y: area.y,
-            width: (area.width as f32 * 0.20) as u16,
-            height: area.height - 2,
-        };
-        self.projects.render(&mut self.buffer, &projects_area);
+        self.buffer.resize(&area)?;
+
+        if is_too_small(&area) {
+            self.render_too_small(&area);
+            return self.buffer.flush();
+        }
+
+        // Containers at the top, one line for the mode and one for log messages at the bottom
+        let [main_area, mode_area, log_area] = layout::split(
+            &area,
+            Direction::Vertical,
+            [Constraint::Min(0), Constraint::Fixed(1), Constraint::Fixed(1)],
+        );
+        self.panes_area = main_area;
+        let [projects_area, tasks_area] = self.pane_areas();
+
+        // Display projects, a collapsed pane is not drawn at all
+        if projects_area.width >= 2 {
+            self.projects.render(&mut self.buffer, &projects_area);
+        }
 
         // Display tasks
-        let tasks_area = Rect {
-            x: projects_area.width,
-            y: projects_area.y,
-            width: area.width - projects_area.width,
-            height: projects_area.height,
-        };
-        self.tasks.render(&mut self.buffer, &tasks_area);
+        if tasks_area.width >= 2 {
+            self.tasks.render(&mut self.buffer, &tasks_area);
+        }
 
         // Draw mode
         self.buffer.write_string(
-            area.x,
-            area.height - 2,
-            self.mode(area.width),
+            mode_area.x,
+            mode_area.y,
+            self.mode(mode_area.width),
         );
 
         // Draw log line
         self.buffer.write_string(
-            area.x,
-            area.height - 1,
-            build_row(vec![(&self.log_message, area.width as usize - 2)]),
+            log_area.x,
+            log_area.y,
+            build_row(vec![(&self.log_message, (log_area.width as usize).saturating_sub(2))]),
         );
 
         if self.input_mode == InputMode::Save
             || self.input_mode == InputMode::Quit
             || self.input_mode == InputMode::Delete
+            || self.input_mode == InputMode::Reload
+            || self.input_mode == InputMode::Export
         {
             self.message_box.render(&mut self.buffer, &area);
         }
 
-        if self.input_mode == InputMode::Insert || self.input_mode == InputMode::Rename {
+        if self.input_mode == InputMode::Insert
+            || self.input_mode == InputMode::Rename
+            || self.input_mode == InputMode::NewWorkspace
+        {
             self.line_input.render(&mut self.buffer, &area);
         }
 
+        if self.input_mode == InputMode::History || self.input_mode == InputMode::Workspaces {
+            self.list_popup.render(&mut self.buffer, &area);
+        }
+
+        if self.input_mode == InputMode::Passphrase {
+            self.passphrase_input.render(&mut self.buffer, &area);
+        }
+
         // Write to stdout
         self.buffer.flush()?;
 
         Ok(())
     }
 
+    // Placeholder that is shown instead of the application if the terminal is too small
+    fn render_too_small(&mut self, area: &Rect) {
+        let lines = [
+            String::from("Terminal too small"),
+            format!("{}x{} < {}x{}", area.width, area.height, MIN_WIDTH, MIN_HEIGHT),
+        ];
+        let message_area = layout::centered(
+            area,
+            Constraint::Fixed(lines[0].len() as u16),
+            Constraint::Fixed(lines.len() as u16),
+        );
+        for (i, line) in lines.into_iter().enumerate() {
+            self.buffer.write_string(
+                message_area.x,
+                message_area.y + i as u16,
+                build_row(vec![(&line, message_area.width as usize)]).yellow(),
+            );
+        }
+    }
+
+    // The key handling keeps its nested ifs, newer clippy versions would collapse them
+    #[allow(clippy::collapsible_if, clippy::collapsible_match)]
     async fn handle_key_event(&mut self, key: &KeyEvent) {
+        // The shortcut of a message box button selects the button and confirms it
+        let shortcut = if (self.input_mode == InputMode::Save
+            || self.input_mode == InputMode::Quit
+            || self.input_mode == InputMode::Delete
+            || self.input_mode == InputMode::Reload
+            || self.input_mode == InputMode::Export)
+            && let Some(index) = self.message_box.shortcut(key)
+        {
+            self.message_box.select(index);
+            Some(KeyEvent::from(KeyCode::Enter))
+        } else {
+            None
+        };
+        let key = shortcut.as_ref().unwrap_or(key);
+
+        // Keys that are different while an old version is viewed
+        if self.input_mode == InputMode::Normal && self.history_view.is_some() {
+            match key.code {
+                KeyCode::Char('q') => return self.close_history_view(),
+                KeyCode::Char('r') if !self.read_only() => return self.restore_history_view(),
+                _ => {}
+            }
+        }
+
+        // Ignore keys that modify the data if another instance holds the lock
+        // or if an old version is viewed
+        if (self.read_only() || self.history_view.is_some())
+            && self.input_mode == InputMode::Normal
+            && matches!(
+                key.code,
+                KeyCode::Char('i' | 'd' | 'e' | 'r' | 's' | 'S') | KeyCode::Delete
+            )
+        {
+            if self.read_only() {
+                self.log("Read-only, the data is locked by another instance");
+            } else {
+                self.log("Viewing an old version, press r to restore it or q to go back");
+            }
+            return;
+        }
+
         // Handle keys with modifiers
         if self.input_mode == InputMode::Normal {
             if let KeyEvent {
@@ -193,6 +466,8 @@ impl TodoApp {
                     self.input_mode = InputMode::Save;
                     self.message_box
                         .set_question("Are you sure that you want to save?");
+                    // Saving was asked for explicitly, so confirming it is the default
+                    self.message_box.select(0);
                 }
             }
         }
@@ -205,11 +480,29 @@ impl TodoApp {
                         self.input_mode = InputMode::Quit;
                         self.message_box
                             .set_question("Do you want to save your changes before quitting?");
+                        self.message_box.set_buttons(&QUIT_BUTTONS, QUIT_SAVE);
                     } else {
                         self.quit = true
                     }
                 }
-                KeyCode::Char('i') => self.input_mode = InputMode::Insert,
+                KeyCode::Char('i') => {
+                    self.input_mode = InputMode::Insert;
+                    self.open_title_input(String::new());
+                }
+                KeyCode::Char('x') if self.storage.encrypted() => {
+                    self.input_mode = InputMode::Export;
+                    self.message_box.set_question(
+                        "The data is encrypted but the export is plain text. Export anyway?",
+                    );
+                }
+                KeyCode::Char('x') => self.export_markdown(),
+                KeyCode::Char('h') => self.open_history(),
+                KeyCode::Char('S') => self.sync(),
+                KeyCode::Char('w') => self.open_workspaces(),
+                KeyCode::Char('<') => self.resize_panes(-PANE_STEP, 0),
+                KeyCode::Char('>') => self.resize_panes(PANE_STEP, 0),
+                KeyCode::Char('-') => self.resize_panes(0, -PANE_STEP),
+                KeyCode::Char('+') => self.resize_panes(0, PANE_STEP),
                 KeyCode::Char('d') => {
                     if self.tasks.is_focused() {
                         self.dirty = true;
@@ -221,32 +514,31 @@ impl TodoApp {
                     }
                 }
                 KeyCode::Char('e') => {
-                    if self.tasks.is_focused() {
+                    if self.tasks.is_focused() && self.edit_task(self.tasks.selected()).await {
                         self.dirty = true;
-                        self.edit_task(self.tasks.selected()).await;
                     }
                 }
                 KeyCode::Char('r') => {
                     if self.projects.current_project().is_some() {
                         if self.projects.is_focused() {
                             self.input_mode = InputMode::Rename;
-                            self.line_input.set_value(
-                                self.projects
-                                    .current_project()
-                                    .expect("Could not retrieve currently selected project")
-                                    .title
-                                    .clone(),
-                            );
+                            let title = self
+                                .projects
+                                .current_project()
+                                .expect("Could not retrieve currently selected project")
+                                .title
+                                .clone();
+                            self.open_title_input(title);
                         } else if self.tasks.len() > 0 {
                             self.input_mode = InputMode::Rename;
-                            self.line_input.set_value(
-                                self.projects
-                                    .current_project()
-                                    .expect("Could not retrieve currently selected project")
-                                    .tasks[self.tasks.selected()]
+                            let title = self
+                                .projects
+                                .current_project()
+                                .expect("Could not retrieve currently selected project")
+                                .tasks[self.tasks.selected()]
                                 .title
-                                .clone(),
-                            );
+                                .clone();
+                            self.open_title_input(title);
                         }
                     }
                 }
@@ -299,6 +591,7 @@ impl TodoApp {
                     if self.line_input.value().len() > 3 {
                         self.input_mode = InputMode::Normal;
                         self.dirty = true;
+                        self.remember_input(self.line_input.value());
 
                         if self.projects.is_focused() {
                             // Add project to projects list
@@ -326,6 +619,7 @@ impl TodoApp {
                     if self.line_input.value().len() > 3 {
                         self.input_mode = InputMode::Normal;
                         self.dirty = true;
+                        self.remember_input(self.line_input.value());
 
                         if self.projects.is_focused() {
                             self.projects
@@ -336,8 +630,7 @@ impl TodoApp {
                             self.projects
                                 .current_project()
                                 .expect("Could not retrieve currently selected project")
-                                .tasks[self.tasks.selected()]
-                            .title = self.line_input.value();
+                                .rename_task(self.tasks.selected(), self.line_input.value());
                             self.update_tasks();
                         }
                         self.line_input.close();
@@ -384,51 +677,658 @@ impl TodoApp {
                 _ => {}
             },
             InputMode::Quit => match key.code {
+                KeyCode::Esc | KeyCode::Enter => {
+                    let choice = if key.code == KeyCode::Enter {
+                        self.message_box.selected()
+                    } else {
+                        QUIT_CANCEL
+                    };
+                    match choice {
+                        QUIT_SAVE => {
+                            self.save();
+                            // Don't lose the modifications if they could not be saved
+                            self.quit = !self.dirty;
+                        }
+                        QUIT_DISCARD => self.quit = true,
+                        _ => {}
+                    }
+
+                    if !self.quit {
+                        self.input_mode = InputMode::Normal;
+                        self.message_box.close();
+                    }
+                }
+                _ => {}
+            },
+            InputMode::Passphrase => match key.code {
+                KeyCode::Esc => match self.previous_workspace.take() {
+                    Some((name, cipher)) => {
+                        self.input_mode = InputMode::Normal;
+                        self.passphrase_input.close();
+                        self.switch_workspace(&name).await;
+                        // Don't ask again for the passphrase of the previous workspace
+                        self.previous_workspace = None;
+                        if let Some(cipher) = cipher {
+                            self.open_encrypted(cipher);
+                        }
+                    }
+                    None => self.quit = true,
+                },
+                KeyCode::Enter => self.unlock(),
+                _ => {}
+            },
+            InputMode::Workspaces => match key.code {
                 KeyCode::Esc => {
                     self.input_mode = InputMode::Normal;
-                    self.message_box.close();
+                    self.list_popup.close();
                 }
                 KeyCode::Enter => {
-                    if self.message_box.accepted() {
-                        self.save();
+                    self.input_mode = InputMode::Normal;
+                    // The last entry creates a new workspace
+                    match self.list_popup.selected() {
+                        Some(index) if index < self.workspaces.len() => {
+                            self.switch_workspace(&self.workspaces[index].clone()).await;
+                        }
+                        Some(_) => self.input_mode = InputMode::NewWorkspace,
+                        None => {}
+                    }
+                    self.list_popup.close();
+                }
+                _ => {}
+            },
+            InputMode::NewWorkspace => match key.code {
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.line_input.close();
+                }
+                KeyCode::Enter => match workspace::validate(&self.line_input.value()) {
+                    Ok(()) => {
+                        self.input_mode = InputMode::Normal;
+                        self.switch_workspace(&self.line_input.value()).await;
+                        self.line_input.close();
+                    }
+                    Err(error) => self.log(&error),
+                },
+                _ => {}
+            },
+            InputMode::History => match key.code {
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.list_popup.close();
+                }
+                KeyCode::Enter => {
+                    self.input_mode = InputMode::Normal;
+                    if let Some(index) = self.list_popup.selected() {
+                        self.view_commit(index);
                     }
-                    self.quit = true;
+                    self.list_popup.close();
                 }
                 _ => {}
             },
+            InputMode::Export => match key.code {
+                KeyCode::Esc | KeyCode::Enter => {
+                    self.input_mode = InputMode::Normal;
+                    if key.code == KeyCode::Enter && self.message_box.accepted() {
+                        self.export_markdown();
+                    }
+                    self.message_box.close();
+                }
+                _ => {}
+            },
+            InputMode::Reload => match key.code {
+                KeyCode::Esc | KeyCode::Enter => {
+                    self.input_mode = InputMode::Normal;
+
+                    let choice = if key.code == KeyCode::Enter {
+                        self.message_box.selected()
+                    } else {
+                        RELOAD_KEEP_MINE
+                    };
+                    match choice {
+                        RELOAD_MERGE => self.merge_projects(),
+                        RELOAD_TAKE_THEIRS => self.reload_projects(),
+                        _ => {
+                            // Don't ask again for this change, the next save overwrites it
+                            // Loading lets the storage know what it has to overwrite
+                            match self.storage.load() {
+                                Ok(projects) => self.base = projects,
+                                Err(error) => self.log(&error.to_string()),
+                            }
+                            self.data_modified = modification_time(self.storage.path());
+                            self.log("Kept local changes");
+                        }
+                    }
+
+                    self.message_box.close();
+                }
+                _ => {}
+            },
+        }
+    }
+
+    // React to the data file being changed by someone else
+    fn handle_data_change(&mut self) {
+        if self.dirty {
+            self.input_mode = InputMode::Reload;
+            self.message_box
+                .set_question("The data file was changed on disk. What do you want to do?");
+            self.message_box.set_buttons(&RELOAD_BUTTONS, RELOAD_KEEP_MINE);
+            self.message_box.show();
+        } else {
+            self.reload_projects();
+        }
+    }
+
+    // Replace the current state with the data file
+    // The current state is kept if the data file can't be read
+    fn reload_projects(&mut self) {
+        let selected = self.projects.current_project().map(|project| project.id);
+        let projects_focused = self.projects.is_focused();
+
+        if !self.import_projects() {
+            return;
+        }
+        if let Some(id) = selected {
+            self.projects.select_project(&id);
+        }
+        // Keep the focus where it was
+        if !projects_focused && self.projects.current_project().is_some() {
+            self.projects.set_focus(false);
+            self.tasks.set_focus(true);
+        } else {
+            self.projects.set_focus(true);
+            self.tasks.set_focus(false);
+        }
+        self.update_tasks();
+        self.dirty = false;
+        self.log("Reloaded data file");
+    }
+
+    // Merge the data file into the current state
+    fn merge_projects(&mut self) {
+        match self.storage.load() {
+            Ok(projects) => {
+                self.data_modified = modification_time(self.storage.path());
+                self.projects.merge(&self.base, projects.clone());
+                self.base = projects;
+                self.update_tasks();
+                self.log("Merged changes from the data file");
+            }
+            Err(error) => self.log(&error.to_string()),
+        }
+    }
+
+    // Show the list of saved versions
+    fn open_history(&mut self) {
+        if !history::enabled() {
+            self.log("History is not enabled, run `wetopla history init` to enable it");
+            return;
+        }
+
+        match history::log() {
+            Ok(commits) => {
+                let items = commits
+                    .iter()
+                    .map(|commit| format!("{}  {}  {}", commit.id, commit.date, commit.message))
+                    .collect();
+                self.history_commits = commits.into_iter().map(|commit| commit.id).collect();
+                self.list_popup.set_items("History", items);
+                self.input_mode = InputMode::History;
+            }
+            Err(error) => self.log(&error),
+        }
+    }
+
+    // Split the area of the panes into the project pane and the task pane
+    fn pane_areas(&self) -> [Rect; 2] {
+        layout::split(
+            &self.panes_area,
+            Direction::Horizontal,
+            [Constraint::Percentage(self.panes.projects), Constraint::Min(0)],
+        )
+    }
+
+    // Change the sizes of the panes by some percent
+    fn resize_panes(&mut self, projects: i16, task_list: i16) {
+        let resize = |size: u16, change: i16| (size as i16 + change).clamp(0, 100) as u16;
+        self.panes.projects = resize(self.panes.projects, projects);
+        self.panes.task_list = resize(self.panes.task_list, task_list);
+        self.tasks.set_list_height(self.panes.task_list);
+        self.save_panes();
+    }
+
+    // Kind of title that the line input is used for in the insert and rename mode
+    fn input_kind(&self) -> InputKind {
+        if self.projects.is_focused() {
+            InputKind::Project
+        } else {
+            InputKind::Task
+        }
+    }
+
+    // Prepare the line input for a project or task title
+    // Titles of encrypted data are not remembered since the history is not encrypted
+    fn open_title_input(&mut self, value: String) {
+        let kind = self.input_kind();
+        self.line_input.set_value(value);
+        if !self.storage.encrypted() {
+            self.line_input
+                .set_history(InputHistory::load().entries(kind).to_vec());
+        }
+        match kind {
+            InputKind::Project => self.line_input.set_completions(
+                self.projects
+                    .projects()
+                    .iter()
+                    .map(|project| project.title.clone())
+                    .collect(),
+            ),
+            InputKind::Task => self.line_input.set_completions(self.tags()),
+        }
+    }
+
+    fn remember_input(&mut self, value: String) {
+        if self.storage.encrypted() {
+            return;
+        }
+        if let Err(error) = InputHistory::update(self.input_kind(), value) {
+            self.log(&format!("Could not save input history: {}", error));
+        }
+    }
+
+    // Words that task titles can be completed with
+    // These are the project tags of todo.txt and the tags (+tag, @context, #tag) of existing tasks
+    fn tags(&self) -> Vec<String> {
+        let projects = self.projects.projects();
+        let mut tags: Vec<String> = projects
+            .iter()
+            .map(|project| project.tag())
+            .filter(|tag| !tag.is_empty())
+            .map(|tag| format!("+{}", tag))
+            .collect();
+        for task in projects.iter().flat_map(|project| &project.tasks) {
+            tags.extend(
+                task.title
+                    .split_whitespace()
+                    .filter(|word| word.len() > 1 && word.starts_with(['+', '@', '#']))
+                    .map(str::to_owned),
+            );
+        }
+        tags.sort();
+        tags.dedup();
+
+        tags
+    }
+
+    fn save_panes(&mut self) {
+        let panes = self.panes;
+        if let Err(error) = State::update(|state| state.panes = panes) {
+            self.log(&format!("Could not save state: {}", error));
+        }
+    }
+
+    // Dividers between panes can be dragged with the left mouse button
+    async fn handle_mouse_event(&mut self, mouse: &MouseEvent) {
+        // Only the placeholder is drawn, so there is nothing to click on
+        if is_too_small(&terminal::size()) {
+            self.dragging = None;
+            return;
+        }
+        let [projects_area, tasks_area] = self.pane_areas();
+        let [task_list_area, _] = self.tasks.split(&tasks_area);
+        let area = &self.panes_area;
+        // Percent of `length` that ends right before `position`
+        let percentage = |position: u16, start: u16, length: u16| {
+            let size = position.saturating_sub(start).min(length) as u32;
+            (size * 100).div_ceil(length.max(1) as u32) as u16
+        };
+
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let task_list_end = task_list_area.y + task_list_area.height;
+                // The borders on both sides of a divider can be grabbed
+                // Popups are drawn above the dividers, so they can't be grabbed then
+                self.dragging = if self.input_mode != InputMode::Normal {
+                    None
+                } else if (mouse.column == tasks_area.x
+                    || mouse.column + 1 == tasks_area.x)
+                    && mouse.row >= area.y
+                    && mouse.row < area.y + area.height
+                {
+                    Some(Divider::Projects)
+                } else if (mouse.row == task_list_end || mouse.row + 1 == task_list_end)
+                    && mouse.column >= tasks_area.x
+                    && mouse.column < tasks_area.x + tasks_area.width
+                {
+                    Some(Divider::TaskList)
+                } else {
+                    None
+                };
+                if self.dragging.is_none() {
+                    self.handle_click(mouse, &projects_area, &tasks_area).await;
+                }
+            }
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+                let code = if mouse.kind == MouseEventKind::ScrollUp {
+                    KeyCode::Up
+                } else {
+                    KeyCode::Down
+                };
+                if self.input_mode == InputMode::History || self.input_mode == InputMode::Workspaces
+                {
+                    self.list_popup.process_input(&KeyEvent::from(code));
+                    self.redraw = true;
+                } else if self.input_mode == InputMode::Normal {
+                    // Scrolling moves the selection of the pane below the mouse
+                    if projects_area.contains(mouse.column, mouse.row) {
+                        self.projects.set_focus(true);
+                        self.tasks.set_focus(false);
+                    } else if tasks_area.contains(mouse.column, mouse.row)
+                        && self.projects.current_project().is_some()
+                    {
+                        self.projects.set_focus(false);
+                        self.tasks.set_focus(true);
+                    } else {
+                        return;
+                    }
+                    self.handle_key_event(&KeyEvent::from(code)).await;
+                    self.redraw = true;
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => match self.dragging {
+                Some(Divider::Projects) => {
+                    self.panes.projects = percentage(mouse.column, area.x, area.width);
+                    self.redraw = true;
+                }
+                Some(Divider::TaskList) => {
+                    self.panes.task_list =
+                        percentage(mouse.row, tasks_area.y, tasks_area.height);
+                    self.tasks.set_list_height(self.panes.task_list);
+                    self.redraw = true;
+                }
+                None => {}
+            },
+            // Only save the final sizes
+            MouseEventKind::Up(MouseButton::Left) if self.dragging.take().is_some() => {
+                self.save_panes();
+            }
+            _ => {}
+        }
+    }
+
+    // Clicks select and focus items or press the buttons of the message box
+    // Clicking on the Done column and double clicking a task act like the d and e keys
+    async fn handle_click(&mut self, mouse: &MouseEvent, projects_area: &Rect, tasks_area: &Rect) {
+        let (column, row) = (mouse.column, mouse.row);
+        let code = match self.input_mode {
+            InputMode::Save
+            | InputMode::Quit
+            | InputMode::Delete
+            | InputMode::Reload
+            | InputMode::Export => {
+                match self.message_box.button_at(&terminal::size(), column, row) {
+                    Some(index) => {
+                        self.message_box.select(index);
+                        Some(KeyCode::Enter)
+                    }
+                    None => None,
+                }
+            }
+            InputMode::Normal => {
+                if let Some(index) = self.projects.item_at(projects_area, column, row) {
+                    self.projects.select(index);
+                    self.projects.set_focus(true);
+                    self.tasks.set_focus(false);
+                    self.update_tasks();
+                    None
+                } else if let Some(index) = self.tasks.item_at(tasks_area, column, row) {
+                    let double_click = matches!(
+                        self.last_click,
+                        Some((time, task)) if task == index && time.elapsed() < DOUBLE_CLICK
+                    );
+                    self.last_click = Some((Instant::now(), index));
+                    self.tasks.select(index);
+                    self.projects.set_focus(false);
+                    self.tasks.set_focus(true);
+                    if self.tasks.is_done_column(tasks_area, column) {
+                        Some(KeyCode::Char('d'))
+                    } else if double_click {
+                        self.last_click = None;
+                        Some(KeyCode::Char('e'))
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+
+        if let Some(code) = code {
+            self.handle_key_event(&KeyEvent::from(code)).await;
+        }
+        self.redraw = true;
+    }
+
+    fn open_workspaces(&mut self) {
+        if self.dirty {
+            self.log("Save before switching workspaces");
+            return;
+        }
+
+        let current = workspace::current();
+        self.workspaces = workspace::list();
+        let mut items: Vec<String> = self
+            .workspaces
+            .iter()
+            .map(|name| {
+                if *name == current {
+                    format!("{} (current)", name)
+                } else {
+                    name.clone()
+                }
+            })
+            .collect();
+        items.push(String::from("New workspace"));
+        self.list_popup.set_items("Workspaces", items);
+        self.input_mode = InputMode::Workspaces;
+    }
+
+    // Close the data of the current workspace and open the data of another one
+    // The workspace is created when it is saved for the first time
+    async fn switch_workspace(&mut self, name: &str) {
+        let previous = workspace::current();
+        if let Err(error) = workspace::select(name) {
+            return self.log(&error);
+        }
+        let storage = match storage::open(None) {
+            Ok(storage) => storage,
+            Err(error) => {
+                workspace::select(&previous).expect("Previous workspace is valid");
+                return self.log(&format!("Could not open data file: {}", error));
+            }
+        };
+        self.log(&format!("Opened workspace {}", name));
+        if let Err(error) = workspace::remember(name) {
+            self.log(&error);
+        }
+
+        // Release the lock of the previous workspace before taking the new one
+        self.lock = None;
+        match Lock::acquire(&Self::lock_path()) {
+            Ok(lock) => self.lock = Some(lock),
+            Err(error) => self.log(&format!("{}, opened read-only", error)),
+        }
+        self.storage = storage;
+        let cipher = self.cipher.take();
+        self.history_view = None;
+        self.data_changed = false;
+        self.projects = ProjectContainer::new(true);
+        self.tasks.set_focus(false);
+        self.update_tasks();
+
+        if !Self::database_path().exists() && crypto::is_encrypted_file(&Self::data_path()) {
+            self.input_mode = InputMode::Passphrase;
+            self.previous_workspace = Some((previous, cipher));
+        } else {
+            self.import_projects();
+        }
+        // The event loop watches the data file of the previous workspace
+        self.stop_event_loop().await;
+        self.event_loop = EventLoop::start(self.storage.path().to_path_buf(), self.data_modified);
+    }
+
+    // Show the projects as they were at the selected commit without changing anything
+    fn view_commit(&mut self, index: usize) {
+        let Some(commit) = self.history_commits.get(index).cloned() else {
+            return;
+        };
+
+        match history::load(&self.history_storage(), &commit) {
+            Ok(mut projects) => {
+                projects.set_focus(true);
+                let current = std::mem::replace(&mut self.projects, projects);
+                // Keep the state from before the first viewed commit
+                let current = match self.history_view.take() {
+                    Some((_, current)) => current,
+                    None => current,
+                };
+                self.history_view = Some((commit, current));
+                self.tasks.set_focus(false);
+                self.update_tasks();
+            }
+            Err(error) => self.log(&error),
+        }
+    }
+
+    // Go back to the state from before viewing the history
+    fn close_history_view(&mut self) {
+        if let Some((_, current)) = self.history_view.take() {
+            self.projects = current;
+            self.projects.set_focus(true);
+            self.tasks.set_focus(false);
+            self.update_tasks();
+        }
+    }
+
+    // Keep the viewed state, it is written with the next save
+    fn restore_history_view(&mut self) {
+        if let Some((commit, _)) = self.history_view.take() {
+            self.dirty = true;
+            self.log(&format!("Restored {}, save to keep it", commit));
+        }
+    }
+
+    // Decrypt the data file with the entered passphrase and load the projects
+    fn unlock(&mut self) {
+        let unlocked = std::fs::read(Self::data_path())
+            .map_err(|error| error.to_string())
+            .and_then(|data| Cipher::unlock(&self.passphrase_input.take_value(), &data));
+
+        match unlocked {
+            Ok(cipher) => {
+                self.previous_workspace = None;
+                self.open_encrypted(cipher);
+            }
+            Err(error) => self.log(&error),
+        }
+    }
+
+    // Load the encrypted data file of the current workspace with this cipher
+    fn open_encrypted(&mut self, cipher: Cipher) {
+        self.storage = Box::new(JsonStorage::new(Self::data_path(), Some(cipher.clone())));
+        self.cipher = Some(cipher);
+        self.input_mode = InputMode::Normal;
+        self.import_projects();
+    }
+
+    // The history always works with the JSON data file
+    fn history_storage(&self) -> JsonStorage {
+        JsonStorage::new(Self::data_path(), self.cipher.clone())
+    }
+
+    // Exchange the history with the remote repository and reload the data
+    fn sync(&mut self) {
+        if !history::enabled() {
+            self.log("History is not enabled, run `wetopla history init <remote>` to enable it");
+        } else if self.dirty {
+            self.log("Save before synchronising");
+        } else {
+            match history::sync(&mut self.history_storage()) {
+                Ok(message) => {
+                    self.reload_projects();
+                    self.log(&message);
+                }
+                Err(error) => self.log(&error),
+            }
+        }
+    }
+
+    // Export the current project as Markdown
+    // All projects are exported if the project list is focused
+    fn export_markdown(&mut self) {
+        let path = Self::data_directory_path().join("export.md");
+        let report = if self.projects.is_focused() {
+            FileFormat::Markdown.export(&self.projects.projects().iter().collect::<Vec<_>>())
+        } else if let Some(project) = self.projects.current_project() {
+            FileFormat::Markdown.export(&[project])
+        } else {
+            return;
+        };
+
+        match std::fs::create_dir_all(Self::data_directory_path())
+            .and_then(|_| std::fs::write(&path, report))
+        {
+            Ok(()) => self.log(&format!("Exported to {}", path.display())),
+            Err(error) => self.log(&error.to_string()),
         }
     }
 
     fn log(&mut self, message: &str) {
         self.log_message = message.to_owned();
         self.log_message_duration = 0;
+        self.redraw = true;
     }
 
-    async fn edit_task(&mut self, task_index: usize) {
-        // Cancel event loop
-        // We cancel it because we will temporarily leave the application and enter
-        // the external text editor
+    // Cancel the event loop and wait until all of its tasks are finished
+    async fn stop_event_loop(&mut self) {
         self.event_loop.abort.cancel();
-        // Wait until all tasks are finished
         loop {
             if self.event_loop.event_rx.recv().await.is_none() {
                 break;
             }
         }
+    }
+
+    // Returns whether the content of the task was changed
+    async fn edit_task(&mut self, task_index: usize) -> bool {
+        // The plain text of encrypted data must not be written to disk
+        if self.storage.encrypted() && project::memory_directory().is_none() {
+            self.log("Cannot edit encrypted tasks, no directory in memory like /dev/shm exists");
+            return false;
+        }
+        // Cancel event loop
+        // We cancel it because we will temporarily leave the application and enter
+        // the external text editor
+        self.stop_event_loop().await;
         // Reset buffers since we are leaving the current screen which invalidates the buffer state
         self.buffer.reset();
         terminal::restore_terminal()
             .expect("Error occured when trying to restore the previous state of the terminal!");
         // Maybe display error in the log bar
-        self.projects
+        let edited = self
+            .projects
             .current_project()
             .expect("Unexpected error: Task is being edited without being assigned to a project")
-            .edit_task(task_index);
+            .edit_task(task_index, self.storage.encrypted());
         terminal::prepare_terminal()
             .expect("Error occured when trying to prepare the terminal for the application!");
         // Restart event loop after entering the application
-        self.event_loop = EventLoop::start();
+        self.event_loop = EventLoop::start(self.storage.path().to_path_buf(), self.data_modified);
         self.update_tasks();
+
+        edited
     }
 
     // Update tasks in task container
@@ -436,51 +1336,71 @@ impl TodoApp {
         self.tasks.set_project(self.projects.current_project());
     }
 
+    // Data directory of the current workspace
     pub fn data_directory_path() -> PathBuf {
-        let home_path = env::var_os("HOME").unwrap();
-        Path::new(&home_path).join(".weeklyplaner")
+        workspace::directory(&workspace::current())
+    }
+
+    pub fn lock_path() -> PathBuf {
+        Self::data_directory_path().join("data.lock")
+    }
+
+    // Whether the data can only be loaded after the passphrase was entered
+    pub fn locked(&self) -> bool {
+        self.input_mode == InputMode::Passphrase
     }
 
-    fn data_path(&self) -> PathBuf {
+    fn read_only(&self) -> bool {
+        self.lock.is_none()
+    }
+
+    pub fn data_path() -> PathBuf {
         Self::data_directory_path().join("data.json")
     }
 
+    pub fn database_path() -> PathBuf {
+        Self::data_directory_path().join("data.db")
+    }
+
     fn save(&mut self) {
-        // Create data directory
-        // If the weeklyplane directory could not be created then just panic
-        std::fs::create_dir_all(Self::data_directory_path()).unwrap();
-        // Open / create data file
-        let data_file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(self.data_path());
-        // Only try to write if file could be created or exists
-        if let Ok(data_file) = data_file {
-            serde_json::to_writer(data_file, &self.projects)
-                .or_else(|x| {
-                    // Print error to log bar
-                    self.log(&x.to_string());
-                    Ok::<(), ()>(())
-                })
-                .unwrap();
-            self.log("Saved");
-        } else {
-            self.log(&data_file.err().unwrap().to_string());
+        match self.storage.save(&self.projects) {
+            Ok(()) => {
+                self.log("Saved");
+                self.dirty = false;
+                self.base = self.projects.clone();
+                self.data_modified = modification_time(self.storage.path());
+                if history::enabled()
+                    && let Err(error) = history::commit(&self.history_storage(), &self.projects)
+                {
+                    self.log(&error);
+                }
+            }
+            // Print error to log bar
+            Err(error) => self.log(&error.to_string()),
         }
-
-        self.dirty = false;
     }
 
-    pub fn import_projects(&mut self) {
-        let data_file = OpenOptions::new().read(true).open(self.data_path());
-
-        if let Ok(data_file) = data_file {
-            if let Ok(reader) = serde_json::from_reader(data_file) {
-                self.projects = reader;
+    // Load the projects from the storage
+    // Returns whether that worked, the current projects are kept otherwise
+    pub fn import_projects(&mut self) -> bool {
+        self.data_modified = modification_time(self.storage.path());
+        match self.storage.load() {
+            Ok(projects) => {
+                self.base = projects.clone();
+                self.projects = projects;
                 self.projects.set_focus(true);
                 self.update_tasks();
+                true
+            }
+            Err(error) => {
+                self.log(&format!("Could not load the data file: {}", error));
+                false
             }
         }
     }
 }
+
+// Whether the terminal is too small to draw the application in
+fn is_too_small(area: &Rect) -> bool {
+    area.width < MIN_WIDTH || area.height < MIN_HEIGHT
+}
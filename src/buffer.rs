@@ -1,118 +1,202 @@
+#[cfg(test)]
+pub mod memory;
+
 use crate::utils::Rect;
 use crossterm::{
     QueueableCommand,
-    cursor::MoveTo,
+    cursor::{Hide, MoveTo, SetCursorStyle, Show},
     style::{ContentStyle, PrintStyledContent, StyledContent},
+    terminal::{Clear, ClearType},
 };
-use std::io::{Stdout, Write, stdout};
+use std::io::{self, Stdout, Write, stdout};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// A cell contains one grapheme cluster
+// A wide grapheme also covers the following cells, they are empty and never drawn
+pub type Cell = StyledContent<String>;
+
+// Target that a buffer draws its cells to
+pub trait Backend {
+    // Draw a cell at an absolute position
+    fn draw(&mut self, x: u16, y: u16, cell: &Cell) -> io::Result<()>;
+    // Make everything that was drawn visible
+    fn flush(&mut self) -> io::Result<()>;
+    // Remove everything that was drawn
+    fn clear(&mut self) -> io::Result<()>;
+    // Show the cursor at an absolute position or hide it
+    fn set_cursor(&mut self, position: Option<(u16, u16)>) -> io::Result<()>;
+}
 
-pub struct Buffer {
-    current: Vec<Vec<StyledContent<char>>>,
-    previous: Vec<Vec<StyledContent<char>>>,
+// Draws to the terminal that the application is running in
+pub struct TerminalBackend {
     stdout: Stdout,
+}
+
+impl Backend for TerminalBackend {
+    fn draw(&mut self, x: u16, y: u16, cell: &Cell) -> io::Result<()> {
+        self.stdout.queue(MoveTo(x, y))?;
+        self.stdout.queue(PrintStyledContent(cell.clone()))?;
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stdout.flush()
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.stdout.queue(Clear(ClearType::All))?;
+
+        Ok(())
+    }
+
+    fn set_cursor(&mut self, position: Option<(u16, u16)>) -> io::Result<()> {
+        if let Some((x, y)) = position {
+            self.stdout.queue(MoveTo(x, y))?;
+            self.stdout.queue(SetCursorStyle::BlinkingBar)?;
+            self.stdout.queue(Show)?;
+        } else {
+            self.stdout.queue(Hide)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Buffer {
+    current: Vec<Vec<Cell>>,
+    previous: Vec<Vec<Cell>>,
+    backend: Box<dyn Backend>,
     area: Rect,
+    // Position of the cursor in the current frame, it is hidden if no widget places it
+    cursor: Option<(u16, u16)>,
 }
 
 impl Buffer {
-    // Create a buffer that has only empty cells
+    // Create a buffer that has only empty cells and draws to the terminal
     pub fn new(area: Rect) -> Self {
+        Self::with_backend(area, Box::new(TerminalBackend { stdout: stdout() }))
+    }
+
+    pub fn with_backend(area: Rect, backend: Box<dyn Backend>) -> Self {
         Buffer {
             current: Buffer::init_buffer(area.height, area.width),
             previous: Buffer::init_buffer(area.height, area.width),
-            stdout: stdout(),
+            backend,
             area,
+            cursor: None,
         }
     }
 
-    pub fn resize(&mut self, area: &Rect) {
+    // Terminals rearrange their content differently when resized, so everything is drawn again
+    pub fn resize(&mut self, area: &Rect) -> io::Result<()> {
         if self.area == *area {
-            return;
+            return Ok(());
         }
         self.area = area.clone();
         self.previous = Buffer::init_buffer(self.area.height, self.area.width);
         self.current = Buffer::init_buffer(self.area.height, self.area.width);
+        self.backend.clear()
     }
 
-    fn init_buffer(rows: u16, columns: u16) -> Vec<Vec<StyledContent<char>>> {
+    fn init_buffer(rows: u16, columns: u16) -> Vec<Vec<Cell>> {
         vec![
-            vec![StyledContent::new(ContentStyle::default(), ' '); columns as usize];
+            vec![StyledContent::new(ContentStyle::default(), String::from(" ")); columns as usize];
             rows as usize
         ]
     }
 
-    fn relative_cell_position(&self, x: u16, y: u16) -> (usize, usize) {
-        if x < self.area.x {
-            panic!("x ({}) cannot be smaller than {}", x, self.area.x);
+    // Position inside the buffer, None if the cell is outside of the area
+    fn relative_cell_position(&self, x: u16, y: u16) -> Option<(usize, usize)> {
+        let x = x.checked_sub(self.area.x)?;
+        let y = y.checked_sub(self.area.y)?;
+        if x >= self.area.width || y >= self.area.height {
+            return None;
         }
 
-        if y < self.area.y {
-            panic!("y ({}) cannot be smaller than {}", y, self.area.y);
-        }
-
-        let (x, y) = (x - self.area.x, y - self.area.y);
+        Some((x as usize, y as usize))
+    }
 
-        if y >= self.area.height {
-            panic!(
-                "Y coordinate outside of useable area ({} > {})",
-                y, self.area.height
-            )
+    // Write the content grapheme by grapheme, content that does not fit is cut off
+    // Content that starts outside of the area is ignored
+    pub fn write_string(&mut self, x: u16, y: u16, content: StyledContent<String>) {
+        let Some((mut x, y)) = self.relative_cell_position(x, y) else {
+            return;
+        };
+        let style = *content.style();
+        let Some(row) = self.current.get_mut(y) else {
+            return;
+        };
+        for grapheme in content.content().graphemes(true) {
+            let width = grapheme.width();
+            // Control characters and lone combining characters don't take any space
+            if width == 0 {
+                continue;
+            }
+            // Don't split a wide grapheme at the end of the row
+            if x + width > row.len() {
+                if x < row.len() {
+                    Self::set_cells(row, x, " ", 1, style);
+                }
+                break;
+            }
+            Self::set_cells(row, x, grapheme, width, style);
+            x += width;
         }
+    }
 
-        if x >= self.area.width {
-            panic!(
-                "X coordinate outside of useable area ({} > {})",
-                x, self.area.width
-            )
+    // Place a grapheme that is `width` cells wide
+    // Wide graphemes that are partly overwritten are replaced by spaces
+    fn set_cells(row: &mut [Cell], x: usize, grapheme: &str, width: usize, style: ContentStyle) {
+        let mut start = x;
+        while start > 0 && row[start].content().is_empty() {
+            start -= 1;
+        }
+        let mut end = x + width;
+        while end < row.len() && row[end].content().is_empty() {
+            end += 1;
+        }
+        for cell in &mut row[start..end] {
+            *cell = StyledContent::new(*cell.style(), String::from(" "));
         }
 
-        (x as usize, y as usize)
+        row[x] = StyledContent::new(style, grapheme.to_owned());
+        for cell in &mut row[x + 1..x + width] {
+            *cell = StyledContent::new(style, String::new());
+        }
     }
 
-    pub fn write_string(&mut self, x: u16, y: u16, content: StyledContent<String>) {
-        let (x, y) = self.relative_cell_position(x, y);
-        for (i, char) in content.content().chars().enumerate() {
-            if self.current.len() > y && self.current[y].len() > x + i {
-                self.current[y][x + i] = StyledContent::new(*content.style(), char);
-            } else {
-                break;
-            }
+    // Show the cursor at the given position after the next flush
+    // A position outside of the area is ignored
+    pub fn set_cursor(&mut self, x: u16, y: u16) {
+        if self.relative_cell_position(x, y).is_some() {
+            self.cursor = Some((x, y));
         }
     }
 
-    pub fn flush(&mut self) -> std::io::Result<()> {
-        'outer: for (row, rows) in self.current.iter().enumerate() {
+    // Draw every cell that changed since the last flush
+    pub fn flush(&mut self) -> io::Result<()> {
+        for (row, rows) in self.current.iter().enumerate() {
             for (column, cell) in rows.iter().enumerate() {
                 if &self.previous[row][column] != cell {
-                    let abs_column = column as u16 + self.area.x;
-                    let abs_row = row as u16 + self.area.y;
-
-                    // Don't show lines that don't fit vertically
-                    if (self.area.y + self.area.height) < abs_row {
-                        continue 'outer;
+                    // The rest of a wide grapheme is drawn together with its first cell
+                    if !cell.content().is_empty() {
+                        self.backend.draw(
+                            column as u16 + self.area.x,
+                            row as u16 + self.area.y,
+                            cell,
+                        )?;
                     }
-
-                    self.stdout.queue(MoveTo(abs_column, abs_row))?;
-
-                    // Truncate lines that don't fit horizontally
-                    if (self.area.x + self.area.width) < abs_column {
-                        self.stdout.queue(MoveTo(abs_column - 4, abs_row))?;
-                        self.stdout.queue(PrintStyledContent(StyledContent::new(
-                            *cell.style(),
-                            "<<<<",
-                        )))?;
-                        continue 'outer;
-                    }
-
-                    self.stdout.queue(PrintStyledContent(*cell))?;
-                    self.previous[row][column] = *cell;
+                    self.previous[row][column] = cell.clone();
                 }
             }
         }
 
         self.current = Buffer::init_buffer(self.area.height, self.area.width);
-        self.stdout.flush()?;
-
-        Ok(())
+        // Drawing moves the cursor, so it is placed again after every frame
+        self.backend.set_cursor(self.cursor.take())?;
+        self.backend.flush()
     }
 
     pub fn reset(&mut self) {
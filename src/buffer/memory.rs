@@ -0,0 +1,388 @@
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+use crossterm::style::{Attribute, Color, ContentStyle, StyledContent};
+use unicode_width::UnicodeWidthStr;
+
+use crate::buffer::{Backend, Buffer, Cell};
+use crate::utils::Rect;
+use crate::widgets::Widget;
+
+// Draws into a grid of cells instead of the terminal, e.g. to compare the output of widgets
+// Clones share the same grid, so one clone can be given to a buffer and the other one read
+#[derive(Clone)]
+pub struct MemoryBackend {
+    cells: Rc<RefCell<Vec<Vec<Cell>>>>,
+    cursor: Rc<RefCell<Option<(u16, u16)>>>,
+}
+
+impl MemoryBackend {
+    pub fn new(width: u16, height: u16) -> Self {
+        let row =
+            vec![StyledContent::new(ContentStyle::default(), String::from(" ")); width as usize];
+        MemoryBackend {
+            cells: Rc::new(RefCell::new(vec![row; height as usize])),
+            cursor: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    // Render a widget into a new grid of the given size
+    pub fn render(widget: &dyn Widget, width: u16, height: u16) -> Self {
+        let area = Rect {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        };
+        let backend = MemoryBackend::new(width, height);
+        let mut buffer = Buffer::with_backend(area.clone(), Box::new(backend.clone()));
+        widget.render(&mut buffer, &area);
+        buffer.flush().expect("Drawing into memory cannot fail");
+
+        backend
+    }
+
+    // The text of every row without trailing spaces
+    pub fn text(&self) -> String {
+        self.cells
+            .borrow()
+            .iter()
+            .map(|row| {
+                let line: String = row.iter().map(|cell| cell.content().as_str()).collect();
+                line.trim_end().to_owned() + "\n"
+            })
+            .collect()
+    }
+
+    // Every row as a list of columns with the same style, e.g. "0-9 yellow on white bold"
+    // Columns with the default style are left out, the position of a visible cursor is appended
+    pub fn styles(&self) -> String {
+        let mut dump = String::new();
+        for (y, row) in self.cells.borrow().iter().enumerate() {
+            let mut runs: Vec<(usize, usize, String)> = Vec::new();
+            for (x, cell) in row.iter().enumerate() {
+                let style = describe_style(cell.style());
+                match runs.last_mut() {
+                    Some((_, end, last)) if *last == style => *end = x,
+                    _ => runs.push((x, x, style)),
+                }
+            }
+            let runs: Vec<String> = runs
+                .into_iter()
+                .filter(|(_, _, style)| !style.is_empty())
+                .map(|(start, end, style)| format!("{}-{} {}", start, end, style))
+                .collect();
+            if !runs.is_empty() {
+                dump.push_str(&format!("{}: {}\n", y, runs.join(", ")));
+            }
+        }
+        if let Some((x, y)) = *self.cursor.borrow() {
+            dump.push_str(&format!("cursor: {},{}\n", x, y));
+        }
+
+        dump
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn draw(&mut self, x: u16, y: u16, cell: &Cell) -> io::Result<()> {
+        let mut cells = self.cells.borrow_mut();
+        let Some(row) = cells.get_mut(y as usize) else {
+            return Ok(());
+        };
+        // A wide grapheme covers the following cells like in a terminal
+        let x = x as usize;
+        let width = cell.content().width().max(1);
+        for (i, target) in row.iter_mut().skip(x).take(width).enumerate() {
+            let content = if i == 0 {
+                cell.content().clone()
+            } else {
+                String::new()
+            };
+            *target = StyledContent::new(*cell.style(), content);
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        for cell in self.cells.borrow_mut().iter_mut().flatten() {
+            *cell = StyledContent::new(ContentStyle::default(), String::from(" "));
+        }
+
+        Ok(())
+    }
+
+    fn set_cursor(&mut self, position: Option<(u16, u16)>) -> io::Result<()> {
+        *self.cursor.borrow_mut() = position;
+
+        Ok(())
+    }
+}
+
+// Short description of a style, empty for the default style
+fn describe_style(style: &ContentStyle) -> String {
+    let color = |color: Option<Color>| match color {
+        None | Some(Color::Reset) => None,
+        Some(color) => Some(format!("{:?}", color).to_lowercase()),
+    };
+
+    let mut parts = Vec::new();
+    if let Some(foreground) = color(style.foreground_color) {
+        parts.push(foreground);
+    }
+    if let Some(background) = color(style.background_color) {
+        parts.push(format!("on {}", background));
+    }
+    for (attribute, name) in [
+        (Attribute::Bold, "bold"),
+        (Attribute::Italic, "italic"),
+        (Attribute::Underlined, "underlined"),
+        (Attribute::Reverse, "reverse"),
+    ] {
+        if style.attributes.has(attribute) {
+            parts.push(name.to_owned());
+        }
+    }
+
+    parts.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone};
+
+    use super::*;
+    use crate::project::{Project, ProjectContainer};
+    use crate::task::{Task, TaskContainer};
+    use crate::widgets::line_input::LineInput;
+    use crate::widgets::list_popup::ListPopup;
+    use crate::widgets::message_box::MessageBox;
+
+    // Two projects with the same two tasks, the first project is selected
+    fn projects() -> ProjectContainer {
+        let date = Local.with_ymd_and_hms(2026, 1, 5, 9, 30, 0).unwrap();
+        let mut tasks = Vec::new();
+        for (title, done) in [("Write report", false), ("Buy milk", true)] {
+            let mut task = Task::new(String::from(title));
+            task.done = done;
+            task.created_at = date;
+            task.modified_at = date;
+            task.content = format!("Content of {}", title);
+            tasks.push(task);
+        }
+        let projects = ["Week 1", "Week 2"]
+            .into_iter()
+            .map(|title| Project {
+                tasks: tasks.clone(),
+                ..Project::new(String::from(title))
+            })
+            .collect();
+
+        ProjectContainer::with_projects(projects, 0, true)
+    }
+
+    fn tasks() -> TaskContainer {
+        let mut tasks = TaskContainer::new(true);
+        tasks.set_project(projects().current_project());
+
+        tasks
+    }
+
+    // Rows of a snapshot like they are returned by `text` or `styles`
+    fn rows(rows: &[&str]) -> String {
+        rows.iter().map(|row| format!("{}\n", row)).collect()
+    }
+
+    #[test]
+    fn projects_snapshot() {
+        let backend = MemoryBackend::render(&projects(), 40, 8);
+
+        assert_eq!(
+            backend.text(),
+            rows(&[
+                "┌───────────Projects───────────────────┐",
+                "│0: Week 1                             │",
+                "│1: Week 2                             │",
+                "│                                      │",
+                "│                                      │",
+                "│                                      │",
+                "│                                      │",
+                "└──────────────────────────────────────┘",
+            ])
+        );
+        assert_eq!(
+            backend.styles(),
+            rows(&[
+                "0: 0-11 yellow, 12-19 bold, 20-39 yellow",
+                "1: 0-0 yellow, 1-38 black on white, 39-39 yellow",
+                "2: 0-0 yellow, 1-38 white, 39-39 yellow",
+                "3: 0-0 yellow, 39-39 yellow",
+                "4: 0-0 yellow, 39-39 yellow",
+                "5: 0-0 yellow, 39-39 yellow",
+                "6: 0-0 yellow, 39-39 yellow",
+                "7: 0-39 yellow",
+            ])
+        );
+    }
+
+    #[test]
+    fn tasks_snapshot() {
+        let backend = MemoryBackend::render(&tasks(), 60, 12);
+
+        assert_eq!(
+            backend.text(),
+            rows(&[
+                "┌────────────────────────Tasks─────────────────────────────┐",
+                "│Title                        Done      Created At         │",
+                "│Write report                 false     05.01.2026 09:30:00│",
+                "│Buy milk                     true      05.01.2026 09:30:00│",
+                "│                                                          │",
+                "└──────────────────────────────────────────────────────────┘",
+                "┌──────────────────────Content─────────────────────────────┐",
+                "│Content of Write report                                   │",
+                "│                                                          │",
+                "│                                                          │",
+                "│                                                          │",
+                "└──────────────────────────────────────────────────────────┘",
+            ])
+        );
+        assert_eq!(
+            backend.styles(),
+            rows(&[
+                "0: 0-24 yellow, 25-29 bold, 30-59 yellow",
+                "1: 0-0 yellow, 1-58 bold, 59-59 yellow",
+                "2: 0-0 yellow, 1-58 black on white, 59-59 yellow",
+                "3: 0-0 yellow, 1-58 green, 59-59 yellow",
+                "4: 0-0 yellow, 59-59 yellow",
+                "5: 0-59 yellow",
+                "6: 23-29 bold",
+            ])
+        );
+    }
+
+    #[test]
+    fn tasks_snapshot_of_a_small_area() {
+        let backend = MemoryBackend::render(&tasks(), 30, 8);
+
+        assert_eq!(
+            backend.text(),
+            rows(&[
+                "┌─────────Tasks──────────────┐",
+                "│Title     Done      Created │",
+                "│Write rep false     05.01.20│",
+                "└────────────────────────────┘",
+                "┌───────Content──────────────┐",
+                "│Content of Write report     │",
+                "│                            │",
+                "└────────────────────────────┘",
+            ])
+        );
+    }
+
+    #[test]
+    fn message_box_snapshot() {
+        let mut message_box = MessageBox::new();
+        message_box.set_question("Do you want to save your changes before quitting?");
+        let backend = MemoryBackend::render(&message_box, 60, 12);
+
+        assert_eq!(
+            backend.text(),
+            rows(&[
+                "",
+                "",
+                "┌──────────────────────────────────────────────────────────┐",
+                "│                                                          │",
+                "│    Do you want to save your changes before quitting?     │",
+                "│                                                          │",
+                "│                                                          │",
+                "│            Yes                            No             │",
+                "└──────────────────────────────────────────────────────────┘",
+                "",
+                "",
+                "",
+            ])
+        );
+        // "No" is selected
+        assert_eq!(
+            backend.styles(),
+            rows(&[
+                "2: 0-59 yellow",
+                "3: 0-0 yellow, 59-59 yellow",
+                "4: 0-0 yellow, 5-53 yellow, 59-59 yellow",
+                "5: 0-0 yellow, 59-59 yellow",
+                "6: 0-0 yellow, 59-59 yellow",
+                "7: 0-0 yellow, 13-15 on white, 44-45 black on white, 59-59 yellow",
+                "8: 0-59 yellow",
+            ])
+        );
+    }
+
+    #[test]
+    fn line_input_snapshot() {
+        let mut line_input = LineInput::new();
+        line_input.set_value(String::from("Write report"));
+        let backend = MemoryBackend::render(&line_input, 60, 12);
+
+        assert_eq!(
+            backend.text(),
+            rows(&[
+                "",
+                "",
+                "",
+                "",
+                "┌────────────Insert value here─────────────────────────────┐",
+                "│Write report                                              │",
+                "└──────────────────────────────────────────────────────────┘",
+                "",
+                "",
+                "",
+                "",
+                "",
+            ])
+        );
+        // The cursor is placed after the text
+        assert_eq!(
+            backend.styles(),
+            rows(&[
+                "4: 0-12 yellow, 13-29 bold, 30-59 yellow",
+                "5: 0-0 yellow, 59-59 yellow",
+                "6: 0-59 yellow",
+                "cursor: 13,5",
+            ])
+        );
+    }
+
+    #[test]
+    fn list_popup_snapshot() {
+        let mut list_popup = ListPopup::new();
+        list_popup.set_items(
+            "Projects",
+            vec![String::from("Week 1"), String::from("Week 2")],
+        );
+        let backend = MemoryBackend::render(&list_popup, 60, 12);
+
+        assert_eq!(
+            backend.text(),
+            rows(&[
+                "",
+                "",
+                "",
+                "               ┌──────Projects──────────────┐",
+                "               │Week 1                      │",
+                "               │Week 2                      │",
+                "               │                            │",
+                "               │                            │",
+                "               └────────────────────────────┘",
+                "",
+                "",
+                "",
+            ])
+        );
+    }
+}
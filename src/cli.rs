@@ -0,0 +1,781 @@
+mod output;
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use chrono::{Local, NaiveDate};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use zeroize::Zeroizing;
+
+use crate::application::TodoApp;
+use crate::crypto::{self, Cipher};
+use crate::formats::FileFormat;
+use crate::history;
+use crate::lock::Lock;
+use crate::project::ProjectContainer;
+use crate::storage::{self, JsonStorage, ProjectEntry, Query, SqliteStorage, Storage, TaskEntry};
+use crate::state::InputHistory;
+use crate::utils::{id_matches, short_id};
+use crate::workspace;
+use output::Format;
+
+const USAGE: &str = "Usage: wetopla [COMMAND] [OPTIONS]
+
+Without a command the interactive planner is started.
+
+Commands:
+    add <project> <title>   Add a task to a project (the project is created if missing)
+    list [project]          List all tasks or only the tasks of one project
+    done <id>               Mark a task as done
+    move <id> <project>     Move a task to another project
+    projects                List all projects
+    show <id>               Show a task including its content
+    export <format>         Export projects to a file format
+    import <format> <file>  Import tasks from a file, use - to read from stdin
+    migrate                 Move the data from data.json to an SQLite database
+    history                 List the saved versions of the data
+    history init [remote]   Keep a history of the data in a git repository
+    sync                    Exchange the history with the remote repository
+    encrypt                 Encrypt the data file with a passphrase
+    decrypt                 Remove the encryption of the data file
+    workspaces              List all workspaces
+    help                    Print this message
+
+Options for all commands and the interactive planner:
+    --workspace <name>      Use this workspace instead of the last used one
+
+Options for list, projects, show and import --dry-run:
+    --format <format>       Output format: plain (default), table or json
+
+Options for list:
+    --project <project>     Only list tasks of this project
+    --done                  Only list tasks that are done
+    --pending               Only list tasks that are not done
+    --since <YYYY-MM-DD>    Only list tasks created on or after this date
+    --until <YYYY-MM-DD>    Only list tasks created on or before this date
+    --search <words>        Only list tasks with words that start with these words
+
+Options for export:
+    --project <project>     Only export this project
+    --output <file>         Write to a file instead of stdout
+
+Options for import:
+    --project <project>     Add all tasks to this project (the project is created if missing)
+    --dry-run               Only show what would be imported
+
+File formats:
+    markdown (export only), todotxt, ics, csv, taskwarrior, org
+
+The passphrase of an encrypted data file is read from the terminal or from the
+environment variable WETOPLA_PASSPHRASE.
+
+Projects can be referenced by their title or their id.
+Tasks are referenced by their id. Any unique prefix of an id can be used.
+
+Arguments after -- are never treated as options, e.g. wetopla add Inbox -- --help me";
+
+// Remove `--workspace <name>` from the arguments and use that workspace
+// The option is accepted anywhere before `--` since it also applies to the TUI which has no
+// subcommand
+pub fn select_workspace(args: &mut Vec<String>) -> Result<(), String> {
+    match take_workspace(args)? {
+        Some(name) => workspace::select(&name),
+        None => Ok(()),
+    }
+}
+
+// Remove `--workspace <name>` from the arguments and return the name
+fn take_workspace(args: &mut Vec<String>) -> Result<Option<String>, String> {
+    let Some(position) = args
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .position(|arg| arg == "--workspace")
+    else {
+        return Ok(None);
+    };
+    if position + 1 >= args.len() || args[position + 1] == "--" {
+        return Err(String::from("Missing value for --workspace"));
+    }
+
+    let name = args.remove(position + 1);
+    args.remove(position);
+    Ok(Some(name))
+}
+
+// Subcommands that can be run without starting the TUI
+pub enum Command {
+    Add {
+        project: String,
+        title: String,
+    },
+    List {
+        filter: Filter,
+        format: Format,
+    },
+    Done {
+        id: String,
+    },
+    Move {
+        id: String,
+        project: String,
+    },
+    Projects {
+        format: Format,
+    },
+    Show {
+        id: String,
+        format: Format,
+    },
+    Export {
+        format: FileFormat,
+        project: Option<String>,
+        output: Option<PathBuf>,
+    },
+    Import {
+        format: FileFormat,
+        file: PathBuf,
+        project: Option<String>,
+        dry_run: bool,
+        format_preview: Format,
+    },
+    Migrate,
+    History,
+    HistoryInit {
+        remote: Option<String>,
+    },
+    Sync,
+    Encrypt,
+    Decrypt,
+    Workspaces,
+    Help,
+}
+
+// Restricts which tasks are listed
+#[derive(Default)]
+pub struct Filter {
+    project: Option<String>,
+    done: Option<bool>,
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+    search: Option<String>,
+}
+
+impl Command {
+    // Parse the command line arguments (without the program name)
+    // None is returned if no subcommand was given
+    pub fn parse(args: &[String]) -> Result<Option<Self>, String> {
+        let Some((name, args)) = args.split_first() else {
+            return Ok(None);
+        };
+
+        // Options that are accepted by the command
+        let options: &[&str] = match name.as_str() {
+            "list" => &[
+                "--format",
+                "--project",
+                "--done",
+                "--pending",
+                "--since",
+                "--until",
+                "--search",
+            ],
+            "projects" | "show" => &["--format"],
+            "export" => &["--project", "--output"],
+            "import" => &["--format", "--project", "--dry-run"],
+            _ => &[],
+        };
+
+        let mut positional = Vec::new();
+        let mut filter = Filter::default();
+        let mut format = None;
+        let mut output = None;
+        let mut dry_run = false;
+        let mut options_ended = false;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            // Everything after `--` is positional, e.g. a task title that starts with `--`
+            if options_ended || !arg.starts_with("--") {
+                positional.push(arg.to_owned());
+                continue;
+            }
+            if arg == "--" {
+                options_ended = true;
+                continue;
+            }
+            if !options.contains(&arg.as_str()) {
+                return Err(format!(
+                    "Invalid option for {}: {}, use -- to pass it as argument",
+                    name, arg
+                ));
+            }
+
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("Missing value for {}", arg))
+            };
+            match arg.as_str() {
+                "--format" => format = Some(Format::parse(value()?)?),
+                "--project" => filter.project = Some(value()?.to_owned()),
+                "--done" => filter.done = Some(true),
+                "--pending" => filter.done = Some(false),
+                "--since" => filter.since = Some(parse_date(value()?)?),
+                "--until" => filter.until = Some(parse_date(value()?)?),
+                "--search" => filter.search = Some(value()?.to_owned()),
+                "--output" => output = Some(PathBuf::from(value()?)),
+                "--dry-run" => dry_run = true,
+                _ => unreachable!("Option was checked above"),
+            }
+        }
+        if name == "import" && format.is_some() && !dry_run {
+            return Err(String::from("--format can only be used with --dry-run"));
+        }
+        let format = format.unwrap_or(Format::Plain);
+
+        let command = match (name.as_str(), positional.as_slice()) {
+            ("add", [project, title @ ..]) if !title.is_empty() => Command::Add {
+                project: project.to_owned(),
+                title: title.join(" "),
+            },
+            ("list", []) => Command::List { filter, format },
+            ("list", [project]) if filter.project.is_none() => Command::List {
+                filter: Filter {
+                    project: Some(project.to_owned()),
+                    ..filter
+                },
+                format,
+            },
+            ("done", [id]) => Command::Done { id: id.to_owned() },
+            ("move", [id, project]) => Command::Move {
+                id: id.to_owned(),
+                project: project.to_owned(),
+            },
+            ("projects", []) => Command::Projects { format },
+            ("show", [id]) => Command::Show {
+                id: id.to_owned(),
+                format,
+            },
+            ("export", [file_format]) => Command::Export {
+                format: FileFormat::parse(file_format)?,
+                project: filter.project,
+                output,
+            },
+            ("import", [file_format, file]) => Command::Import {
+                format: FileFormat::parse(file_format)?,
+                file: PathBuf::from(file),
+                project: filter.project,
+                dry_run,
+                format_preview: format,
+            },
+            ("migrate", []) => Command::Migrate,
+            ("history", []) => Command::History,
+            ("history", [init]) if init == "init" => Command::HistoryInit { remote: None },
+            ("history", [init, remote]) if init == "init" => Command::HistoryInit {
+                remote: Some(remote.to_owned()),
+            },
+            ("sync", []) => Command::Sync,
+            ("encrypt", []) => Command::Encrypt,
+            ("decrypt", []) => Command::Decrypt,
+            ("workspaces", []) => Command::Workspaces,
+            ("help" | "-h" | "--help", []) => Command::Help,
+            _ => {
+                return Err(format!(
+                    "Invalid command: {}\n\n{}",
+                    args_to_string(name, &positional),
+                    USAGE
+                ));
+            }
+        };
+
+        Ok(Some(command))
+    }
+
+    // Whether the command writes to the data file
+    fn writes(&self) -> bool {
+        match self {
+            Command::Add { .. }
+            | Command::Done { .. }
+            | Command::Move { .. }
+            | Command::Migrate
+            | Command::HistoryInit { .. }
+            | Command::Sync
+            | Command::Encrypt
+            | Command::Decrypt => true,
+            Command::Import { dry_run, .. } => !dry_run,
+            _ => false,
+        }
+    }
+
+    pub fn run(self) -> Result<(), String> {
+        // Hold the lock until the data file was written
+        let _lock =
+            if self.writes() {
+                Some(Lock::acquire(&TodoApp::lock_path()).map_err(|error| {
+                    format!("{}, close the other instance and try again", error)
+                })?)
+            } else {
+                None
+            };
+        let cipher = match self {
+            Command::Help | Command::Workspaces => None,
+            _ => unlock()?,
+        };
+        let mut storage = storage::open(cipher.clone())
+            .map_err(|error| format!("Could not open data file: {}", error))?;
+
+        match self {
+            Command::Add { project, title } => {
+                if title.trim().is_empty() {
+                    return Err(String::from("Task title cannot be empty"));
+                }
+                let mut projects = load_projects(storage.as_mut())?;
+                let project_index = find_or_add_project(&mut projects, project)?;
+                let id = projects
+                    .project_mut(project_index)
+                    .expect("Project was just looked up")
+                    .add_task(title);
+                println!("{}", short_id(&id));
+                save_projects(storage.as_mut(), &projects, &cipher)?;
+            }
+            Command::List { filter, format } => {
+                let project = match &filter.project {
+                    Some(name) => Some(find_project_entry(
+                        &project_entries(storage.as_mut())?,
+                        name,
+                    )?),
+                    None => None,
+                };
+                let query = Query {
+                    project,
+                    done: filter.done,
+                    since: filter.since,
+                    until: filter.until,
+                    search: filter.search,
+                };
+                let tasks = storage
+                    .tasks(&query)
+                    .map_err(|error| format!("Could not read data file: {}", error))?;
+                output::print_tasks(format, &tasks);
+            }
+            Command::Done { id } => {
+                let mut projects = load_projects(storage.as_mut())?;
+                let (project_index, task_index) = find_task(&projects, &id)?;
+                let task = &mut projects
+                    .project_mut(project_index)
+                    .expect("Project was just looked up")
+                    .tasks[task_index];
+                if !task.done {
+                    task.done = true;
+                    task.modified_at = Local::now();
+                }
+                save_projects(storage.as_mut(), &projects, &cipher)?;
+            }
+            Command::Move { id, project } => {
+                let mut projects = load_projects(storage.as_mut())?;
+                let (project_index, task_index) = find_task(&projects, &id)?;
+                let target_index = find_project(&projects, &project)?;
+                projects.move_task(project_index, task_index, target_index);
+                save_projects(storage.as_mut(), &projects, &cipher)?;
+            }
+            Command::Projects { format } => {
+                output::print_projects(format, &project_entries(storage.as_mut())?);
+            }
+            Command::Show { id, format } => {
+                let projects = load_projects(storage.as_mut())?;
+                let (project_index, task_index) = find_task(&projects, &id)?;
+                let project = &projects.projects()[project_index];
+                output::print_task(format, &TaskEntry::new(project, &project.tasks[task_index]));
+            }
+            Command::Export {
+                format,
+                project,
+                output,
+            } => {
+                let projects = load_projects(storage.as_mut())?;
+                let selected: Vec<_> = match project {
+                    Some(name) => vec![&projects.projects()[find_project(&projects, &name)?]],
+                    None => projects.projects().iter().collect(),
+                };
+                write_output(output, &format.export(&selected))?;
+            }
+            Command::Import {
+                format,
+                file,
+                project,
+                dry_run,
+                format_preview,
+            } => {
+                let imported = format.import(&read_input(&file)?)?;
+                let mut projects = load_projects(storage.as_mut())?;
+                let target_index = match project {
+                    Some(name) => Some(find_or_add_project(&mut projects, name)?),
+                    None => None,
+                };
+
+                for error in &imported.errors {
+                    eprintln!("{}", error);
+                }
+
+                let existing: HashSet<_> = projects
+                    .projects()
+                    .iter()
+                    .flat_map(|project| project.tasks.iter().map(|task| task.id))
+                    .collect();
+                let count = projects.import(imported.projects, target_index);
+                if dry_run {
+                    // Only the tasks that were added, duplicates are skipped
+                    let preview: Vec<TaskEntry> = projects
+                        .projects()
+                        .iter()
+                        .flat_map(|project| {
+                            project
+                                .tasks
+                                .iter()
+                                .filter(|task| !existing.contains(&task.id))
+                                .map(move |task| TaskEntry::new(project, task))
+                        })
+                        .collect();
+                    output::print_tasks(format_preview, &preview);
+                    // The output has to stay valid JSON
+                    if !matches!(format_preview, output::Format::Json) {
+                        println!("Would import {} tasks (dry run)", count);
+                    }
+                } else {
+                    save_projects(storage.as_mut(), &projects, &cipher)?;
+                    println!("Imported {} tasks", count);
+                }
+            }
+            Command::Migrate => migrate(&cipher)?,
+            Command::History => {
+                if !history::enabled() {
+                    return Err(String::from(
+                        "History is not enabled, run `wetopla history init` first",
+                    ));
+                }
+                for commit in history::log()? {
+                    println!("{} {} {}", commit.id, commit.date, commit.message);
+                }
+            }
+            Command::HistoryInit { remote } => {
+                if TodoApp::database_path().exists() {
+                    return Err(String::from("History is only supported for data.json"));
+                }
+                history::init(&mut history_storage(&cipher), remote.as_deref())?
+            }
+            Command::Sync => {
+                if !history::enabled() {
+                    return Err(String::from(
+                        "History is not enabled, run `wetopla history init <remote>` first",
+                    ));
+                }
+                println!("{}", history::sync(&mut history_storage(&cipher))?);
+            }
+            Command::Encrypt => {
+                if TodoApp::database_path().exists() {
+                    return Err(String::from("Encryption is only supported for data.json"));
+                }
+                if cipher.is_some() {
+                    return Err(String::from("The data file is already encrypted"));
+                }
+                // Encrypting only affects new commits, the old ones can't be rewritten here
+                if history::enabled() {
+                    eprintln!(
+                        "Warning: the history in {} keeps the earlier versions in plain text",
+                        TodoApp::data_directory_path().display()
+                    );
+                }
+                let passphrase = read_passphrase("New passphrase")?;
+                if passphrase.is_empty() {
+                    return Err(String::from("The passphrase cannot be empty"));
+                }
+                if read_passphrase("Repeat passphrase")? != passphrase {
+                    return Err(String::from("The passphrases do not match"));
+                }
+
+                let projects = load_projects(storage.as_mut())?;
+                let cipher = Some(Cipher::new(&passphrase)?);
+                save_projects(&mut history_storage(&cipher), &projects, &cipher)?;
+                // Entered titles are not remembered for encrypted data
+                InputHistory::remove()
+                    .map_err(|error| format!("Could not remove input history: {}", error))?;
+                println!("Encrypted {}", TodoApp::data_path().display());
+            }
+            Command::Decrypt => {
+                if cipher.is_none() {
+                    return Err(String::from("The data file is not encrypted"));
+                }
+
+                let projects = load_projects(storage.as_mut())?;
+                // The history still needs the cipher to read the previous version
+                save_projects(&mut history_storage(&None), &projects, &cipher)?;
+                println!("Decrypted {}", TodoApp::data_path().display());
+            }
+            Command::Workspaces => {
+                let current = workspace::current();
+                for name in workspace::list() {
+                    let marker = if name == current { '*' } else { ' ' };
+                    println!("{} {}", marker, name);
+                }
+            }
+            Command::Help => println!("{}", USAGE),
+        }
+
+        Ok(())
+    }
+}
+
+fn args_to_string(name: &str, args: &[String]) -> String {
+    let mut command = name.to_owned();
+    for arg in args {
+        command.push(' ');
+        command.push_str(arg);
+    }
+
+    command
+}
+
+// Read the given file or stdin if the path is "-"
+fn read_input(path: &Path) -> Result<String, String> {
+    if path == Path::new("-") {
+        std::io::read_to_string(std::io::stdin())
+            .map_err(|error| format!("Could not read stdin: {}", error))
+    } else {
+        std::fs::read_to_string(path)
+            .map_err(|error| format!("Could not read {}: {}", path.display(), error))
+    }
+}
+
+// Write to the given file or to stdout if no file was given
+fn write_output(path: Option<PathBuf>, content: &str) -> Result<(), String> {
+    match path {
+        Some(path) => std::fs::write(&path, content)
+            .map_err(|error| format!("Could not write {}: {}", path.display(), error)),
+        None => {
+            print!("{}", content);
+            Ok(())
+        }
+    }
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date: {} (expected YYYY-MM-DD)", date))
+}
+
+fn load_projects(storage: &mut dyn Storage) -> Result<ProjectContainer, String> {
+    storage
+        .load()
+        .map_err(|error| format!("Could not read data file: {}", error))
+}
+
+// `cipher` is used to read the previous version from the history
+fn save_projects(
+    storage: &mut dyn Storage,
+    projects: &ProjectContainer,
+    cipher: &Option<Cipher>,
+) -> Result<(), String> {
+    storage
+        .save(projects)
+        .map_err(|error| format!("Could not write data file: {}", error))?;
+    if history::enabled() {
+        history::commit(&history_storage(cipher), projects)?;
+    }
+
+    Ok(())
+}
+
+// The history always works with the JSON data file
+fn history_storage(cipher: &Option<Cipher>) -> JsonStorage {
+    JsonStorage::new(TodoApp::data_path(), cipher.clone())
+}
+
+// Ask for the passphrase if the data file is encrypted
+fn unlock() -> Result<Option<Cipher>, String> {
+    let data_path = TodoApp::data_path();
+    if TodoApp::database_path().exists() || !crypto::is_encrypted_file(&data_path) {
+        return Ok(None);
+    }
+
+    let data = std::fs::read(&data_path)
+        .map_err(|error| format!("Could not read data file: {}", error))?;
+    Cipher::unlock(&read_passphrase("Passphrase")?, &data).map(Some)
+}
+
+// Read a passphrase from the environment or from the terminal without echoing it
+fn read_passphrase(prompt: &str) -> Result<Zeroizing<String>, String> {
+    if let Ok(passphrase) = std::env::var("WETOPLA_PASSPHRASE") {
+        return Ok(Zeroizing::new(passphrase));
+    }
+
+    eprint!("{}: ", prompt);
+    terminal::enable_raw_mode().map_err(|error| format!("Could not read passphrase: {}", error))?;
+    // Enough room for any passphrase that is typed, so it is not copied when it grows
+    let mut passphrase = Zeroizing::new(String::with_capacity(1024));
+    let result = loop {
+        match event::read() {
+            Ok(Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            })) => match code {
+                KeyCode::Enter => break Ok(passphrase),
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Err(String::from("Cancelled"));
+                }
+                KeyCode::Esc => break Err(String::from("Cancelled")),
+                KeyCode::Char(char) => passphrase.push(char),
+                KeyCode::Backspace => drop(passphrase.pop()),
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(error) => break Err(format!("Could not read passphrase: {}", error)),
+        }
+    };
+    let _ = terminal::disable_raw_mode();
+    eprintln!();
+
+    result
+}
+
+fn project_entries(storage: &mut dyn Storage) -> Result<Vec<ProjectEntry>, String> {
+    storage
+        .projects()
+        .map_err(|error| format!("Could not read data file: {}", error))
+}
+
+// Copy all projects from the JSON data file into a new SQLite database
+// The data file is kept as a backup
+fn migrate(cipher: &Option<Cipher>) -> Result<(), String> {
+    let data_path = TodoApp::data_path();
+    let database_path = TodoApp::database_path();
+    if database_path.exists() {
+        return Err(format!("Already migrated to {}", database_path.display()));
+    }
+    if history::enabled() {
+        return Err(String::from("The history only supports data.json"));
+    }
+    if cipher.is_some() {
+        return Err(String::from(
+            "Encrypted data cannot be migrated, run `wetopla decrypt` first",
+        ));
+    }
+    if !data_path.exists() {
+        return Err(format!(
+            "Nothing to migrate: {} does not exist",
+            data_path.display()
+        ));
+    }
+
+    let projects = load_projects(&mut JsonStorage::new(data_path.clone(), None))?;
+    let mut database = SqliteStorage::open(&database_path)
+        .map_err(|error| format!("Could not create database: {}", error))?;
+    save_projects(&mut database, &projects, &None)?;
+
+    let backup_path = data_path.with_extension("json.bak");
+    std::fs::rename(&data_path, &backup_path)
+        .map_err(|error| format!("Could not move {}: {}", data_path.display(), error))?;
+    println!(
+        "Migrated {} projects to {}, the old data file was moved to {}",
+        projects.projects().len(),
+        database_path.display(),
+        backup_path.display()
+    );
+
+    Ok(())
+}
+
+fn find_or_add_project(projects: &mut ProjectContainer, name: String) -> Result<usize, String> {
+    if name.trim().is_empty() {
+        return Err(String::from("Project name cannot be empty"));
+    }
+    if projects.find_project(&name).is_empty() {
+        projects.add_project(name);
+        Ok(projects.projects().len() - 1)
+    } else {
+        find_project(projects, &name)
+    }
+}
+
+// Same as `find_project` but for projects that were read with `Storage::projects`
+fn find_project_entry(projects: &[ProjectEntry], name: &str) -> Result<uuid::Uuid, String> {
+    let mut matches: Vec<&ProjectEntry> = projects.iter().filter(|p| p.title == name).collect();
+    if matches.is_empty() {
+        matches = projects
+            .iter()
+            .filter(|p| id_matches(&p.id, name))
+            .collect();
+    }
+
+    match matches.as_slice() {
+        [project] => Ok(project.id),
+        [] => Err(format!("Project not found: {}", name)),
+        _ => Err(format!("Project reference is ambiguous: {}", name)),
+    }
+}
+
+fn find_project(projects: &ProjectContainer, name: &str) -> Result<usize, String> {
+    match projects.find_project(name).as_slice() {
+        [project_index] => Ok(*project_index),
+        [] => Err(format!("Project not found: {}", name)),
+        _ => Err(format!("Project reference is ambiguous: {}", name)),
+    }
+}
+
+// Resolve a task id prefix to the project index and task index
+fn find_task(projects: &ProjectContainer, id: &str) -> Result<(usize, usize), String> {
+    match projects.find_task(id).as_slice() {
+        [indices] => Ok(*indices),
+        [] => Err(format!("Task not found: {}", id)),
+        _ => Err(format!("Task id is ambiguous: {}", id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Command>, String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        Command::parse(&args)
+    }
+
+    #[test]
+    fn options_are_only_accepted_by_their_commands() {
+        assert!(parse(&["list", "--done", "--format", "json"]).is_ok());
+        assert!(parse(&["projects", "--done"]).is_err());
+        assert!(parse(&["export", "csv", "--dry-run"]).is_err());
+        assert!(parse(&["import", "csv", "file.csv", "--format", "json"]).is_err());
+        assert!(parse(&["import", "csv", "file.csv", "--dry-run", "--format", "json"]).is_ok());
+        assert!(parse(&["list", "--unknown"]).is_err());
+    }
+
+    #[test]
+    fn workspace_is_not_taken_after_double_dash() {
+        let mut args: Vec<String> = ["add", "Inbox", "--", "--workspace", "foo"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        assert_eq!(take_workspace(&mut args), Ok(None));
+        assert_eq!(args.len(), 5);
+
+        let mut args: Vec<String> = ["--workspace", "work", "add", "Inbox", "--", "--workspace"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        assert_eq!(take_workspace(&mut args), Ok(Some(String::from("work"))));
+        assert_eq!(args, ["add", "Inbox", "--", "--workspace"]);
+    }
+
+    #[test]
+    fn arguments_after_double_dash_are_positional() {
+        assert!(parse(&["add", "Inbox", "fix", "--done", "flag"]).is_err());
+        let Ok(Some(Command::Add { project, title })) =
+            parse(&["add", "Inbox", "--", "fix", "--done", "flag"])
+        else {
+            panic!("Expected an add command");
+        };
+        assert_eq!(project, "Inbox");
+        assert_eq!(title, "fix --done flag");
+    }
+}
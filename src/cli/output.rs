@@ -0,0 +1,166 @@
+use serde::Serialize;
+
+use crate::storage::{ProjectEntry, TaskEntry};
+use crate::utils::{display_width, short_id};
+
+// Version of the JSON output
+// Increase it whenever a field is renamed, removed or changes its meaning
+const SCHEMA_VERSION: u32 = 2;
+const DATE_FORMAT: &str = "%d.%m.%Y %H:%M:%S";
+
+#[derive(Clone, Copy)]
+pub enum Format {
+    Plain,
+    Table,
+    Json,
+}
+
+impl Format {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format {
+            "plain" => Ok(Format::Plain),
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            _ => Err(format!(
+                "Unknown format: {} (expected json, table or plain)",
+                format
+            )),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TaskList<'a> {
+    version: u32,
+    tasks: &'a [TaskEntry],
+}
+
+#[derive(Serialize)]
+struct ProjectList<'a> {
+    version: u32,
+    projects: &'a [ProjectEntry],
+}
+
+#[derive(Serialize)]
+struct SingleTask<'a> {
+    version: u32,
+    task: &'a TaskEntry,
+}
+
+pub fn print_tasks(format: Format, tasks: &[TaskEntry]) {
+    match format {
+        Format::Plain => {
+            for task in tasks {
+                println!(
+                    "{} [{}] {}",
+                    short_id(&task.id),
+                    done_mark(task.done),
+                    task.title
+                );
+            }
+        }
+        Format::Table => print_table(
+            &["ID", "PROJECT", "TITLE", "DONE", "CREATED AT"],
+            tasks
+                .iter()
+                .map(|task| {
+                    vec![
+                        short_id(&task.id),
+                        task.project.clone(),
+                        task.title.clone(),
+                        task.done.to_string(),
+                        task.created_at.format(DATE_FORMAT).to_string(),
+                    ]
+                })
+                .collect(),
+        ),
+        Format::Json => print_json(&TaskList {
+            version: SCHEMA_VERSION,
+            tasks,
+        }),
+    }
+}
+
+pub fn print_projects(format: Format, projects: &[ProjectEntry]) {
+    match format {
+        Format::Plain => {
+            for project in projects {
+                println!(
+                    "{}: {} ({} tasks)",
+                    short_id(&project.id),
+                    project.title,
+                    project.tasks
+                );
+            }
+        }
+        Format::Table => print_table(
+            &["ID", "TITLE", "TASKS", "DONE"],
+            projects
+                .iter()
+                .map(|project| {
+                    vec![
+                        short_id(&project.id),
+                        project.title.clone(),
+                        project.tasks.to_string(),
+                        project.done_tasks.to_string(),
+                    ]
+                })
+                .collect(),
+        ),
+        Format::Json => print_json(&ProjectList {
+            version: SCHEMA_VERSION,
+            projects,
+        }),
+    }
+}
+
+pub fn print_task(format: Format, task: &TaskEntry) {
+    match format {
+        Format::Plain | Format::Table => {
+            println!("ID:          {}", task.id);
+            println!("Project:     {}", task.project);
+            println!("Title:       {}", task.title);
+            println!("Done:        {}", task.done);
+            println!("Created at:  {}", task.created_at.format(DATE_FORMAT));
+            println!("Modified at: {}", task.modified_at.format(DATE_FORMAT));
+            if !task.content.is_empty() {
+                println!("\n{}", task.content.trim_end());
+            }
+        }
+        Format::Json => print_json(&SingleTask {
+            version: SCHEMA_VERSION,
+            task,
+        }),
+    }
+}
+
+fn done_mark(done: bool) -> &'static str {
+    if done { "x" } else { " " }
+}
+
+fn print_json<T: Serialize>(value: &T) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).expect("Output is always serializable")
+    );
+}
+
+// Print rows as columns that are aligned to the widest cell
+fn print_table(header: &[&str], rows: Vec<Vec<String>>) {
+    let mut widths: Vec<usize> = header.iter().map(|cell| display_width(cell)).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(display_width(cell));
+        }
+    }
+
+    let header = header.iter().map(|cell| cell.to_string()).collect();
+    for row in std::iter::once(header).chain(rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{}{}", cell, " ".repeat(width - display_width(cell))))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+}
@@ -0,0 +1,131 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use zeroize::Zeroizing;
+
+// Encryption of the data file
+//
+// An encrypted file starts with MAGIC, followed by the salt of the key derivation and the
+// nonce. The rest is the data encrypted with XChaCha20-Poly1305. The key is derived from
+// the passphrase with Argon2id. The header is authenticated together with the data.
+
+const MAGIC: &[u8] = b"wetopla-encrypted-1\n";
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 24;
+const HEADER_LENGTH: usize = MAGIC.len() + SALT_LENGTH + NONCE_LENGTH;
+const KEY_LENGTH: usize = 32;
+
+// The passphrase and the key are wiped from memory when they are dropped
+#[derive(Clone)]
+pub struct Cipher {
+    passphrase: Zeroizing<String>,
+    salt: [u8; SALT_LENGTH],
+    // Key that was derived from the passphrase and the salt
+    key: Zeroizing<[u8; KEY_LENGTH]>,
+}
+
+impl Cipher {
+    // Create a cipher with a new salt, used to encrypt data for the first time
+    pub fn new(passphrase: &str) -> Result<Self, String> {
+        let mut salt = [0; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+
+        Ok(Cipher {
+            passphrase: Zeroizing::new(passphrase.to_owned()),
+            salt,
+            key: Zeroizing::new(derive_key(passphrase, &salt)?),
+        })
+    }
+
+    // Create the cipher for encrypted data and check that the passphrase is correct
+    pub fn unlock(passphrase: &str, data: &[u8]) -> Result<Self, String> {
+        let salt = read_salt(data)?;
+        let cipher = Cipher {
+            passphrase: Zeroizing::new(passphrase.to_owned()),
+            salt,
+            key: Zeroizing::new(derive_key(passphrase, &salt)?),
+        };
+        cipher.decrypt(data)?;
+
+        Ok(cipher)
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let mut data = Vec::with_capacity(HEADER_LENGTH + plaintext.len());
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&self.salt);
+        data.extend_from_slice(&nonce);
+
+        let ciphertext = XChaCha20Poly1305::new(&(*self.key).into())
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: &data,
+                },
+            )
+            .expect("Encrypting in memory cannot fail");
+        data.extend_from_slice(&ciphertext);
+
+        data
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let salt = read_salt(data)?;
+        // The data might have been encrypted with another salt by another instance
+        let key = Zeroizing::new(if salt == self.salt {
+            *self.key
+        } else {
+            derive_key(&self.passphrase, &salt)?
+        });
+        let (header, ciphertext) = data.split_at(HEADER_LENGTH);
+        let nonce = XNonce::from_slice(&header[MAGIC.len() + SALT_LENGTH..]);
+
+        XChaCha20Poly1305::new(&(*key).into())
+            .decrypt(
+                nonce,
+                Payload {
+                    msg: ciphertext,
+                    aad: header,
+                },
+            )
+            .map_err(|_| String::from("Wrong passphrase or damaged data"))
+    }
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+// Check whether a file is encrypted without reading all of it
+pub fn is_encrypted_file(path: &Path) -> bool {
+    let mut magic = [0; MAGIC.len()];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok_and(|_| is_encrypted(&magic))
+}
+
+fn read_salt(data: &[u8]) -> Result<[u8; SALT_LENGTH], String> {
+    if !is_encrypted(data) || data.len() < HEADER_LENGTH {
+        return Err(String::from("The data is not encrypted"));
+    }
+
+    Ok(data[MAGIC.len()..MAGIC.len() + SALT_LENGTH]
+        .try_into()
+        .expect("Salt has a fixed length"))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; KEY_LENGTH], String> {
+    let mut key = [0; KEY_LENGTH];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|error| format!("Could not derive key: {}", error))?;
+
+    Ok(key)
+}
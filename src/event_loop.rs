@@ -1,6 +1,9 @@
 use crossterm::event::Event as CrosstermEvent;
 use crossterm::event::EventStream;
 use futures::{StreamExt, future::FutureExt};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use tokio::{
     sync::mpsc::{UnboundedReceiver, unbounded_channel},
     time,
@@ -9,8 +12,12 @@ use tokio_util::sync::CancellationToken;
 
 #[derive(Debug)]
 pub enum Event {
-    Draw,
-    Key(CrosstermEvent),
+    // Sent every second, e.g. to let log messages expire
+    Tick,
+    // Key presses, resizes and all other events of the terminal
+    Terminal(CrosstermEvent),
+    // The data file was modified by someone else
+    DataChanged,
 }
 
 pub struct EventLoop {
@@ -20,30 +27,46 @@ pub struct EventLoop {
     pub abort: CancellationToken,
 }
 
+// Return the last modification time of a file or None if it does not exist
+pub fn modification_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
 impl EventLoop {
-    pub fn start() -> Self {
+    // `data_path` is watched for changes
+    // `data_modified` is the modification time of the data file that the application knows about
+    pub fn start(data_path: PathBuf, data_modified: Option<SystemTime>) -> Self {
         let (event_tx, event_rx) = unbounded_channel::<Event>();
         let abort_token = CancellationToken::new();
         // Keep a reference and listen on it
         let cloned_abort = abort_token.clone();
 
-        // Initial draw event
-        // This is sent so we start drawring directly instead of waiting 1 sec at the beginning
-        event_tx
-            .send(Event::Draw)
-            .expect("Unexpected error when trying to send a draw event");
         tokio::spawn(async move {
             let mut stream = EventStream::new();
+            let mut last_modified = data_modified;
+            // Unlike a sleep in the loop, the interval is not delayed by terminal events
+            let mut interval = time::interval(time::Duration::from_secs(1));
+            interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+            // The first tick completes immediately
+            interval.tick().await;
             loop {
                 tokio::select! {
-                    // Send a draw event every second
-                    _ = time::sleep(time::Duration::from_secs(1)) => {
-                        event_tx.send(Event::Draw).expect("Unexpected error when trying to send a draw event");
+                    // Send a tick event every second
+                    _ = interval.tick() => {
+                        event_tx.send(Event::Tick).expect("Unexpected error when trying to send a tick event");
+                        // Check whether the data file was modified since the last check
+                        let modified = modification_time(&data_path);
+                        if modified != last_modified {
+                            last_modified = modified;
+                            event_tx.send(Event::DataChanged).expect("Unexpected error when trying to send a data changed event");
+                        }
                     }
                     // Listen on the crossterm event stream and send events
                     key = stream.next().fuse() => {
-                        if let Some(Ok(key)) = key {
-                            event_tx.send(Event::Key(key)).expect("Unexpected error when trying to send a key event");
+                        if let Some(Ok(event)) = key {
+                            event_tx.send(Event::Terminal(event)).expect("Unexpected error when trying to send a terminal event");
                         }
                     }
                     // Check if the token was canceled
@@ -0,0 +1,84 @@
+pub mod csv;
+pub mod ical;
+pub mod markdown;
+pub mod org;
+pub mod taskwarrior;
+pub mod todotxt;
+
+use crate::project::Project;
+use crate::task::Task;
+
+// Project that is used for imported tasks that don't belong to any project
+pub const DEFAULT_PROJECT: &str = "Inbox";
+
+// File formats that projects can be converted to
+#[derive(Clone, Copy)]
+pub enum FileFormat {
+    Markdown,
+    TodoTxt,
+    Ical,
+    Csv,
+    Taskwarrior,
+    Org,
+}
+
+// Result of parsing a file
+#[derive(Default)]
+pub struct Imported {
+    pub projects: Vec<Project>,
+    // Entries that could not be imported
+    pub errors: Vec<String>,
+}
+
+impl Imported {
+    // Add a task to the project with the given title and create it if needed
+    pub fn add_task(&mut self, project_title: String, task: Task) {
+        match self.projects.iter_mut().find(|p| p.title == project_title) {
+            Some(project) => project.tasks.push(task),
+            None => {
+                let mut project = Project::new(project_title);
+                project.tasks.push(task);
+                self.projects.push(project);
+            }
+        }
+    }
+}
+
+impl FileFormat {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format {
+            "markdown" | "md" => Ok(FileFormat::Markdown),
+            "todotxt" | "todo.txt" => Ok(FileFormat::TodoTxt),
+            "ical" | "ics" => Ok(FileFormat::Ical),
+            "csv" => Ok(FileFormat::Csv),
+            "taskwarrior" | "tw" => Ok(FileFormat::Taskwarrior),
+            "org" => Ok(FileFormat::Org),
+            _ => Err(format!(
+                "Unknown file format: {} (expected markdown, todotxt, ics, csv, taskwarrior or org)",
+                format
+            )),
+        }
+    }
+
+    pub fn export(&self, projects: &[&Project]) -> String {
+        match self {
+            FileFormat::Markdown => markdown::export(projects),
+            FileFormat::TodoTxt => todotxt::export(projects),
+            FileFormat::Ical => ical::export(projects),
+            FileFormat::Csv => csv::export(projects),
+            FileFormat::Taskwarrior => taskwarrior::export(projects),
+            FileFormat::Org => org::export(projects),
+        }
+    }
+
+    pub fn import(&self, text: &str) -> Result<Imported, String> {
+        match self {
+            FileFormat::Markdown => Err(String::from("Importing Markdown is not supported")),
+            FileFormat::TodoTxt => todotxt::import(text),
+            FileFormat::Ical => ical::import(text),
+            FileFormat::Csv => csv::import(text),
+            FileFormat::Taskwarrior => taskwarrior::import(text),
+            FileFormat::Org => org::import(text),
+        }
+    }
+}
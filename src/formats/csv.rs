@@ -0,0 +1,181 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+
+use crate::formats::{DEFAULT_PROJECT, Imported};
+use crate::project::Project;
+use crate::task::Task;
+
+// Comma separated values with one task per row
+//
+// The first row is treated as a header if one of its cells is the name of a title column.
+// Columns with unknown names are ignored then. Otherwise the columns are expected in the
+// same order as they are exported.
+
+#[derive(Clone, Copy, PartialEq)]
+enum Column {
+    Project,
+    Title,
+    Done,
+    Created,
+    Modified,
+    Content,
+}
+
+const COLUMNS: [Column; 6] = [
+    Column::Project,
+    Column::Title,
+    Column::Done,
+    Column::Created,
+    Column::Modified,
+    Column::Content,
+];
+
+impl Column {
+    fn name(&self) -> &'static str {
+        match self {
+            Column::Project => "project",
+            Column::Title => "title",
+            Column::Done => "done",
+            Column::Created => "created",
+            Column::Modified => "modified",
+            Column::Content => "content",
+        }
+    }
+
+    fn from_header(header: &str) -> Option<Self> {
+        match header.trim().to_lowercase().as_str() {
+            "project" => Some(Column::Project),
+            "title" | "task" | "summary" => Some(Column::Title),
+            "done" | "status" | "completed" => Some(Column::Done),
+            "created" | "created at" | "created_at" => Some(Column::Created),
+            "modified" | "modified at" | "modified_at" => Some(Column::Modified),
+            "content" | "description" | "notes" => Some(Column::Content),
+            _ => None,
+        }
+    }
+}
+
+pub fn export(projects: &[&Project]) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    writer
+        .write_record(COLUMNS.iter().map(Column::name))
+        .expect("Writing to memory cannot fail");
+    for project in projects {
+        for task in &project.tasks {
+            writer
+                .write_record([
+                    project.title.as_str(),
+                    task.title.as_str(),
+                    if task.done { "true" } else { "false" },
+                    &task.created_at.to_rfc3339(),
+                    &task.modified_at.to_rfc3339(),
+                    task.content.as_str(),
+                ])
+                .expect("Writing to memory cannot fail");
+        }
+    }
+
+    String::from_utf8(writer.into_inner().expect("Writing to memory cannot fail"))
+        .expect("CSV is built from valid UTF-8")
+}
+
+pub fn import(text: &str) -> Result<Imported, String> {
+    let mut imported = Imported::default();
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let mut records = reader.records().peekable();
+
+    // Detect the header, None is a column that is ignored
+    let mut columns: Vec<Option<Column>> = COLUMNS.into_iter().map(Some).collect();
+    if let Some(Ok(first)) = records.peek() {
+        let header: Vec<Option<Column>> = first.iter().map(Column::from_header).collect();
+        if header.contains(&Some(Column::Title)) {
+            columns = header;
+            records.next();
+        }
+    }
+
+    for record in records {
+        let record = match record {
+            Ok(record) => record,
+            Err(error) => {
+                imported.errors.push(error.to_string());
+                continue;
+            }
+        };
+        let line = record.position().map_or(0, |position| position.line());
+        // Skip empty lines
+        if record.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+
+        match parse_record(&columns, &record) {
+            Ok((project_title, task)) => imported.add_task(project_title, task),
+            Err(error) => imported.errors.push(format!("Line {}: {}", line, error)),
+        }
+    }
+
+    Ok(imported)
+}
+
+fn parse_record(
+    columns: &[Option<Column>],
+    record: &csv::StringRecord,
+) -> Result<(String, Task), String> {
+    let mut project_title = String::from(DEFAULT_PROJECT);
+    let mut task = Task::new(String::new());
+    let mut modified_at = None;
+
+    for (column, cell) in columns.iter().zip(record.iter()) {
+        // The content is kept as it is since it can span multiple lines
+        if *column == Some(Column::Content) {
+            task.content = cell.to_owned();
+            continue;
+        }
+        let cell = cell.trim();
+        match column {
+            Some(Column::Project) if !cell.is_empty() => project_title = cell.to_owned(),
+            Some(Column::Title) => task.title = cell.to_owned(),
+            Some(Column::Done) => task.done = parse_done(cell)?,
+            Some(Column::Created) if !cell.is_empty() => task.created_at = parse_date(cell)?,
+            Some(Column::Modified) if !cell.is_empty() => modified_at = Some(parse_date(cell)?),
+            _ => {}
+        }
+    }
+
+    if task.title.is_empty() {
+        return Err(String::from("Missing title"));
+    }
+    task.modified_at = modified_at.unwrap_or(task.created_at);
+
+    Ok((project_title, task))
+}
+
+fn parse_done(value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "y" | "x" | "1" | "done" | "completed" => Ok(true),
+        "false" | "no" | "n" | "" | "0" | "pending" => Ok(false),
+        _ => Err(format!("Invalid done value: {}", value)),
+    }
+}
+
+// Parse a date in RFC 3339, ISO or the format that is displayed in the TUI
+fn parse_date(value: &str) -> Result<DateTime<Local>, String> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date.with_timezone(&Local));
+    }
+
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%d.%m.%Y %H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            ["%Y-%m-%d", "%d.%m.%Y"]
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .and_then(|date| Local.from_local_datetime(&date).earliest())
+        .ok_or_else(|| format!("Invalid date: {}", value))
+}
@@ -0,0 +1,252 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use uuid::Uuid;
+
+use crate::formats::{DEFAULT_PROJECT, Imported};
+use crate::project::Project;
+use crate::task::Task;
+
+// iCalendar format (RFC 5545)
+//
+// Every task is written as a VTODO component. The project title is written to
+// CATEGORIES, which is also used to assign imported tasks to a project.
+
+const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+// Lines longer than this (in bytes) have to be folded
+const MAX_LINE_LENGTH: usize = 75;
+
+pub fn export(projects: &[&Project]) -> String {
+    let mut calendar = String::new();
+
+    push_line(&mut calendar, "BEGIN:VCALENDAR");
+    push_line(&mut calendar, "VERSION:2.0");
+    push_line(&mut calendar, "PRODID:-//wetopla//wetopla//EN");
+    for project in projects {
+        for task in &project.tasks {
+            push_line(&mut calendar, "BEGIN:VTODO");
+            push_line(&mut calendar, &format!("UID:{}", task.id));
+            push_line(
+                &mut calendar,
+                &format!("DTSTAMP:{}", format_date(&Local::now())),
+            );
+            push_line(&mut calendar, &format!("SUMMARY:{}", escape(&task.title)));
+            if !task.content.is_empty() {
+                push_line(
+                    &mut calendar,
+                    &format!("DESCRIPTION:{}", escape(&task.content)),
+                );
+            }
+            push_line(
+                &mut calendar,
+                &format!("CATEGORIES:{}", escape(&project.title)),
+            );
+            push_line(
+                &mut calendar,
+                &format!("CREATED:{}", format_date(&task.created_at)),
+            );
+            push_line(
+                &mut calendar,
+                &format!("LAST-MODIFIED:{}", format_date(&task.modified_at)),
+            );
+            if task.done {
+                push_line(&mut calendar, "STATUS:COMPLETED");
+                push_line(
+                    &mut calendar,
+                    &format!("COMPLETED:{}", format_date(&task.modified_at)),
+                );
+            } else {
+                push_line(&mut calendar, "STATUS:NEEDS-ACTION");
+            }
+            push_line(&mut calendar, "END:VTODO");
+        }
+    }
+    push_line(&mut calendar, "END:VCALENDAR");
+
+    calendar
+}
+
+pub fn import(text: &str) -> Result<Imported, String> {
+    let mut imported = Imported::default();
+    // Task that is currently parsed and the title of its project
+    let mut current: Option<(String, Task)> = None;
+    // Number of components inside of the task, e.g. VALARM, their properties are ignored
+    let mut depth = 0;
+
+    for line in unfold(text) {
+        let Some((name, value)) = split_property(&line) else {
+            continue;
+        };
+
+        if current.is_some() {
+            match name.as_str() {
+                "BEGIN" => depth += 1,
+                "END" if depth > 0 => depth -= 1,
+                _ => {}
+            }
+            if depth > 0 || (name == "END" && value != "VTODO") {
+                continue;
+            }
+        }
+
+        match (name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value == "VTODO" => {
+                current = Some((String::from(DEFAULT_PROJECT), Task::new(String::new())));
+            }
+            ("END", Some(_)) if value == "VTODO" => {
+                let (project_title, mut task) = current.take().expect("A task is being parsed");
+                if task.modified_at < task.created_at {
+                    task.modified_at = task.created_at;
+                }
+                imported.add_task(project_title, task);
+            }
+            ("UID", Some((_, task))) => {
+                // Keep our own ids so that importing an export does not duplicate tasks
+                if let Ok(id) = Uuid::parse_str(&value) {
+                    task.id = id;
+                }
+            }
+            ("SUMMARY", Some((_, task))) => task.title = unescape(&value),
+            ("DESCRIPTION", Some((_, task))) => task.content = unescape(&value),
+            ("CATEGORIES", Some((project_title, _))) => {
+                if let Some(category) = split_list(&value).into_iter().next() {
+                    *project_title = category;
+                }
+            }
+            ("CREATED" | "LAST-MODIFIED", Some((_, task))) => match parse_date(&value) {
+                Ok(date) if name == "CREATED" => task.created_at = date,
+                Ok(date) => task.modified_at = date,
+                // The task is imported without the date
+                Err(error) => imported.errors.push(format!("{}: {}", name, error)),
+            },
+            ("STATUS", Some((_, task))) => task.done = value == "COMPLETED",
+            ("COMPLETED", Some((_, task))) => task.done = true,
+            _ => {}
+        }
+    }
+
+    if current.is_some() {
+        return Err(String::from("Unterminated VTODO component"));
+    }
+
+    Ok(imported)
+}
+
+// Add a content line and fold it if it is too long
+fn push_line(calendar: &mut String, line: &str) {
+    let mut length = 0;
+    for char in line.chars() {
+        // Never split a multi-byte character
+        if length + char.len_utf8() > MAX_LINE_LENGTH {
+            calendar.push_str("\r\n ");
+            // The leading space counts towards the line length
+            length = 1;
+        }
+        calendar.push(char);
+        length += char.len_utf8();
+    }
+    calendar.push_str("\r\n");
+}
+
+// Join folded lines
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in text.lines() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(previous)) => previous.push_str(continuation),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+
+    lines
+}
+
+// Split a content line into its upper case name and its value
+// Parameters (e.g. TZID) are ignored
+fn split_property(line: &str) -> Option<(String, String)> {
+    let (name, value) = line.split_once(':')?;
+    let name = name.split(';').next().unwrap_or(name);
+
+    Some((name.to_uppercase(), value.to_owned()))
+}
+
+// Split a comma separated list of values
+fn split_list(value: &str) -> Vec<String> {
+    let mut values = vec![String::new()];
+    let mut chars = value.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    values.last_mut().unwrap().push('\\');
+                    values.last_mut().unwrap().push(escaped);
+                }
+            }
+            ',' => values.push(String::new()),
+            _ => values.last_mut().unwrap().push(char),
+        }
+    }
+
+    values
+        .iter()
+        .map(|value| unescape(value))
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+
+    while let Some(char) = chars.next() {
+        if char == '\\' {
+            match chars.next() {
+                Some('n' | 'N') => unescaped.push('\n'),
+                Some(escaped) => unescaped.push(escaped),
+                None => {}
+            }
+        } else {
+            unescaped.push(char);
+        }
+    }
+
+    unescaped
+}
+
+fn format_date(date: &DateTime<Local>) -> String {
+    date.with_timezone(&Utc)
+        .format(DATE_TIME_FORMAT)
+        .to_string()
+}
+
+// Parse a DATE-TIME in UTC or local time or a DATE
+fn parse_date(value: &str) -> Result<DateTime<Local>, String> {
+    let invalid_date = || format!("Invalid date: {}", value);
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let date =
+            NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").map_err(|_| invalid_date())?;
+        return Ok(Utc.from_utc_datetime(&date).with_timezone(&Local));
+    }
+
+    let date = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y%m%d")
+                .map(|date| date.and_hms_opt(0, 0, 0).expect("Midnight is a valid time"))
+        })
+        .map_err(|_| invalid_date())?;
+
+    Local
+        .from_local_datetime(&date)
+        .earliest()
+        .ok_or_else(invalid_date)
+}
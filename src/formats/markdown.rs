@@ -0,0 +1,46 @@
+use crate::project::Project;
+
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+// Render projects as a Markdown report
+// Every project becomes a heading and its tasks a checklist
+pub fn export(projects: &[&Project]) -> String {
+    let mut report = String::new();
+
+    for project in projects {
+        if !report.is_empty() {
+            report.push('\n');
+        }
+        report.push_str(&format!("# {}\n\n", project.title));
+
+        if project.tasks.is_empty() {
+            report.push_str("*No tasks*\n");
+        }
+        for task in &project.tasks {
+            report.push_str(&format!(
+                "- [{}] {}\n",
+                if task.done { "x" } else { " " },
+                task.title
+            ));
+            report.push_str(&format!(
+                "  *Created: {}, modified: {}*\n",
+                task.created_at.format(DATE_FORMAT),
+                task.modified_at.format(DATE_FORMAT)
+            ));
+            // Indent the content so that it stays part of the list item
+            if !task.content.trim().is_empty() {
+                report.push('\n');
+                for line in task.content.trim_end().lines() {
+                    if line.is_empty() {
+                        report.push('\n');
+                    } else {
+                        report.push_str(&format!("  {}\n", line));
+                    }
+                }
+                report.push('\n');
+            }
+        }
+    }
+
+    report
+}
@@ -0,0 +1,180 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use uuid::Uuid;
+
+use crate::formats::{DEFAULT_PROJECT, Imported};
+use crate::project::Project;
+use crate::task::Task;
+
+// Emacs org-mode
+//
+// Projects are top-level headings and tasks are TODO / DONE sub-headings with a
+// property drawer. The task content is the body of the sub-heading. Content lines that
+// start with "*" are indented by one space so that they are not read as headings.
+
+const TIMESTAMP_FORMAT: &str = "[%Y-%m-%d %a %H:%M]";
+
+pub fn export(projects: &[&Project]) -> String {
+    let mut org = String::new();
+
+    for project in projects {
+        org.push_str(&format!("* {}\n", project.title));
+        for task in &project.tasks {
+            org.push_str(&format!(
+                "** {} {}\n",
+                if task.done { "DONE" } else { "TODO" },
+                task.title
+            ));
+            if task.done {
+                org.push_str(&format!(
+                    "CLOSED: {}\n",
+                    task.modified_at.format(TIMESTAMP_FORMAT)
+                ));
+            }
+            org.push_str(":PROPERTIES:\n");
+            org.push_str(&format!(":ID:       {}\n", task.id));
+            org.push_str(&format!(
+                ":CREATED:  {}\n",
+                task.created_at.format(TIMESTAMP_FORMAT)
+            ));
+            org.push_str(&format!(
+                ":MODIFIED: {}\n",
+                task.modified_at.format(TIMESTAMP_FORMAT)
+            ));
+            org.push_str(":END:\n");
+            for line in task.content.trim_end().lines() {
+                if line.starts_with('*') {
+                    org.push(' ');
+                }
+                org.push_str(line);
+                org.push('\n');
+            }
+        }
+    }
+
+    org
+}
+
+pub fn import(text: &str) -> Result<Imported, String> {
+    let mut imported = Imported::default();
+    let mut project_title = String::from(DEFAULT_PROJECT);
+    // Task that is currently parsed
+    let mut current: Option<Task> = None;
+    let mut in_properties = false;
+
+    for (i, line) in text.lines().enumerate() {
+        let line_number = i + 1;
+
+        if let Some(title) = line.strip_prefix("* ") {
+            finish_task(&mut imported, &project_title, current.take());
+            project_title = title.trim().to_owned();
+            in_properties = false;
+        } else if let Some(heading) = line.strip_prefix("** ") {
+            finish_task(&mut imported, &project_title, current.take());
+            let (done, title) = match heading.split_once(' ') {
+                Some(("TODO", title)) => (false, title),
+                Some(("DONE", title)) => (true, title),
+                _ if heading == "TODO" || heading == "DONE" => (heading == "DONE", ""),
+                _ => (false, heading),
+            };
+            let mut task = Task::new(title.trim().to_owned());
+            task.done = done;
+            current = Some(task);
+            in_properties = false;
+        } else if let Some(task) = current.as_mut() {
+            let trimmed = line.trim();
+            if trimmed == ":PROPERTIES:" && task.content.is_empty() {
+                in_properties = true;
+            } else if in_properties {
+                if trimmed == ":END:" {
+                    in_properties = false;
+                    continue;
+                }
+                let Some((name, value)) = trimmed
+                    .strip_prefix(':')
+                    .and_then(|property| property.split_once(':'))
+                else {
+                    continue;
+                };
+                let value = value.trim();
+                let result = match name.to_uppercase().as_str() {
+                    "ID" => {
+                        if let Ok(id) = Uuid::parse_str(value) {
+                            task.id = id;
+                        }
+                        Ok(())
+                    }
+                    "CREATED" => parse_timestamp(value).map(|date| task.created_at = date),
+                    "MODIFIED" => parse_timestamp(value).map(|date| task.modified_at = date),
+                    _ => Ok(()),
+                };
+                if let Err(error) = result {
+                    imported
+                        .errors
+                        .push(format!("Line {}: {}", line_number, error));
+                }
+            } else if let Some(closed) = trimmed
+                .strip_prefix("CLOSED:")
+                .filter(|_| task.content.is_empty())
+            {
+                task.done = true;
+                match parse_timestamp(closed.trim()) {
+                    Ok(date) => task.modified_at = date,
+                    Err(error) => imported
+                        .errors
+                        .push(format!("Line {}: {}", line_number, error)),
+                }
+            } else {
+                // Undo the indentation of content lines that look like headings
+                let content_line = match line.strip_prefix(' ') {
+                    Some(unindented) if unindented.starts_with('*') => unindented,
+                    _ => line,
+                };
+                task.content.push_str(content_line);
+                task.content.push('\n');
+            }
+        }
+    }
+    finish_task(&mut imported, &project_title, current);
+
+    Ok(imported)
+}
+
+fn finish_task(imported: &mut Imported, project_title: &str, task: Option<Task>) {
+    if let Some(mut task) = task {
+        task.content = task.content.trim_end().to_owned();
+        if task.modified_at < task.created_at {
+            task.modified_at = task.created_at;
+        }
+        imported.add_task(project_title.to_owned(), task);
+    }
+}
+
+// Parse an active or inactive timestamp with or without time
+fn parse_timestamp(timestamp: &str) -> Result<DateTime<Local>, String> {
+    let invalid_timestamp = || format!("Invalid timestamp: {}", timestamp);
+    let inner = timestamp
+        .strip_prefix(['[', '<'])
+        .and_then(|inner| inner.strip_suffix([']', '>']))
+        .ok_or_else(invalid_timestamp)?;
+    // Drop the day name, e.g. "2026-01-05 Mon 10:00"
+    let parts: Vec<&str> = inner
+        .split_whitespace()
+        .filter(|part| !part.chars().all(char::is_alphabetic))
+        .collect();
+
+    let date = match parts.as_slice() {
+        [date] => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0)),
+        [date, time, ..] => {
+            NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").ok()
+        }
+        _ => None,
+    }
+    .ok_or_else(invalid_timestamp)?;
+
+    Local
+        .from_local_datetime(&date)
+        .earliest()
+        .ok_or_else(invalid_timestamp)
+}
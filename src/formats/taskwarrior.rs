@@ -0,0 +1,140 @@
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::formats::{DEFAULT_PROJECT, Imported};
+use crate::project::Project;
+use crate::task::Task;
+
+// JSON as it is written by `task export` and read by `task import`
+//
+// The task content is stored as a single annotation. Imported annotations are joined
+// line by line into the content. Deleted tasks are not imported.
+
+const DATE_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+#[derive(Serialize, Deserialize)]
+struct TaskwarriorTask {
+    uuid: Option<Uuid>,
+    description: String,
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    entry: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<Annotation>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Annotation {
+    entry: Option<String>,
+    description: String,
+}
+
+pub fn export(projects: &[&Project]) -> String {
+    let mut tasks = Vec::new();
+
+    for project in projects {
+        for task in &project.tasks {
+            let mut annotations = Vec::new();
+            if !task.content.trim().is_empty() {
+                annotations.push(Annotation {
+                    entry: Some(format_date(&task.modified_at)),
+                    description: task.content.trim_end().to_owned(),
+                });
+            }
+
+            tasks.push(TaskwarriorTask {
+                uuid: Some(task.id),
+                description: task.title.clone(),
+                status: String::from(if task.done { "completed" } else { "pending" }),
+                project: Some(project.title.clone()),
+                entry: Some(format_date(&task.created_at)),
+                modified: Some(format_date(&task.modified_at)),
+                end: task.done.then(|| format_date(&task.modified_at)),
+                annotations,
+            });
+        }
+    }
+
+    let mut json = serde_json::to_string_pretty(&tasks).expect("Tasks are always serializable");
+    json.push('\n');
+
+    json
+}
+
+pub fn import(text: &str) -> Result<Imported, String> {
+    let mut imported = Imported::default();
+
+    // Older versions of Taskwarrior write one task per line instead of an array
+    let values: Vec<serde_json::Value> = match serde_json::from_str(text) {
+        Ok(values) => values,
+        Err(_) => text
+            .lines()
+            .map(|line| line.trim().trim_end_matches(','))
+            .filter(|line| !line.is_empty() && *line != "[" && *line != "]")
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(|error| format!("Invalid Taskwarrior JSON: {}", error))?,
+    };
+
+    for (i, value) in values.into_iter().enumerate() {
+        let task = serde_json::from_value::<TaskwarriorTask>(value)
+            .map_err(|error| error.to_string())
+            .and_then(convert_task);
+        match task {
+            Ok(Some((project_title, task))) => imported.add_task(project_title, task),
+            Ok(None) => {}
+            Err(error) => imported.errors.push(format!("Task {}: {}", i + 1, error)),
+        }
+    }
+
+    Ok(imported)
+}
+
+// Convert a Taskwarrior task, deleted tasks are skipped
+fn convert_task(tw_task: TaskwarriorTask) -> Result<Option<(String, Task)>, String> {
+    if tw_task.status == "deleted" {
+        return Ok(None);
+    }
+
+    let mut task = Task::new(tw_task.description);
+    if let Some(id) = tw_task.uuid {
+        task.id = id;
+    }
+    task.done = tw_task.status == "completed";
+    if let Some(entry) = &tw_task.entry {
+        task.created_at = parse_date(entry)?;
+    }
+    task.modified_at = match tw_task.modified.as_ref().or(tw_task.end.as_ref()) {
+        Some(modified) => parse_date(modified)?,
+        None => task.created_at,
+    };
+    task.content = tw_task
+        .annotations
+        .into_iter()
+        .map(|annotation| annotation.description)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let project_title = tw_task
+        .project
+        .filter(|project| !project.is_empty())
+        .unwrap_or_else(|| String::from(DEFAULT_PROJECT));
+
+    Ok(Some((project_title, task)))
+}
+
+fn format_date(date: &DateTime<Local>) -> String {
+    date.with_timezone(&Utc).format(DATE_FORMAT).to_string()
+}
+
+fn parse_date(date: &str) -> Result<DateTime<Local>, String> {
+    NaiveDateTime::parse_from_str(date, DATE_FORMAT)
+        .map(|date| Utc.from_utc_datetime(&date).with_timezone(&Local))
+        .map_err(|_| format!("Invalid date: {}", date))
+}
@@ -0,0 +1,285 @@
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+
+use crate::formats::{DEFAULT_PROJECT, Imported};
+use crate::project::Project;
+use crate::task::Task;
+
+// todo.txt format: https://github.com/todotxt/todo.txt
+//
+// A task is written as:
+//   [x COMPLETION-DATE] [(PRIORITY)] [CREATION-DATE] TITLE +PROJECT
+//
+// Contexts (@context) and key-values (due:2026-01-01) are kept as part of the title, so
+// exporting an import results in the same lines. Since tasks have no fields for them, the
+// contexts and the due date are also listed in the content of imported tasks. The task
+// content cannot be represented and is not exported.
+//
+// Spaces in project titles are replaced with underscores because project tags cannot
+// contain spaces, underscores and backslashes of the title are escaped with a backslash.
+// A task belongs to the project of its last project tag since that is where it is exported.
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+pub fn export(projects: &[&Project]) -> String {
+    let mut lines = String::new();
+
+    for project in projects {
+        let tag = project.tag();
+        for task in &project.tasks {
+            let (priority, title) = split_priority(&task.title);
+            let mut line = String::new();
+
+            if task.done {
+                line.push_str(&format!("x {} ", task.modified_at.format(DATE_FORMAT)));
+            }
+            // todo.txt requires the priority to be the first token of an open task
+            if let Some(priority) = priority.filter(|_| !task.done) {
+                line.push_str(priority);
+                line.push(' ');
+            }
+            line.push_str(&task.created_at.format(DATE_FORMAT).to_string());
+            line.push(' ');
+            if let Some(priority) = priority.filter(|_| task.done) {
+                line.push_str(priority);
+                line.push(' ');
+            }
+            line.push_str(title);
+            if !tag.is_empty() {
+                line.push_str(&format!(" +{}", tag));
+            }
+
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+    }
+
+    lines
+}
+
+pub fn import(text: &str) -> Result<Imported, String> {
+    let mut imported = Imported::default();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (project_title, task, errors) = parse_line(line);
+        imported.add_task(project_title, task);
+        imported.errors.extend(
+            errors
+                .into_iter()
+                .map(|error| format!("Line {}: {}", number + 1, error)),
+        );
+    }
+
+    Ok(imported)
+}
+
+// Parse a single todo.txt line into the title of its project and the task
+// Invalid values are reported but don't prevent the task from being imported
+fn parse_line(line: &str) -> (String, Task, Vec<String>) {
+    let mut tokens: Vec<&str> = line.split_whitespace().collect();
+    let mut task = Task::new(String::new());
+    let mut completed_at = None;
+    let mut errors = Vec::new();
+
+    if tokens.first() == Some(&"x") {
+        tokens.remove(0);
+        task.done = true;
+        // A completion date is always followed by the creation date, a single date is the
+        // creation date
+        let dates = tokens.iter().take(2).filter_map(|token| parse_date(token));
+        if dates.count() == 2 {
+            completed_at = parse_date(tokens.remove(0));
+        }
+    }
+    let priority = tokens
+        .first()
+        .filter(|token| split_priority(token).0.is_some())
+        .map(|token| token.to_string());
+    if priority.is_some() {
+        tokens.remove(0);
+    }
+    if let Some(date) = tokens.first().and_then(|token| parse_date(token)) {
+        task.created_at = date;
+        tokens.remove(0);
+    }
+
+    // The last project tag assigns the task to a project, everything else is the title
+    let project_tag = tokens
+        .iter()
+        .rposition(|token| token.len() > 1 && token.starts_with('+'));
+    let project_title = match project_tag {
+        Some(position) => project_title(&tokens.remove(position)[1..]),
+        None => String::from(DEFAULT_PROJECT),
+    };
+
+    let contexts: Vec<&str> = tokens
+        .iter()
+        .copied()
+        .filter(|token| token.len() > 1 && token.starts_with('@'))
+        .collect();
+    if !contexts.is_empty() {
+        task.content
+            .push_str(&format!("Contexts: {}\n", contexts.join(" ")));
+    }
+    for due in tokens.iter().filter_map(|token| token.strip_prefix("due:")) {
+        match parse_date(due) {
+            Some(date) => task
+                .content
+                .push_str(&format!("Due: {}\n", date.format(DATE_FORMAT))),
+            None => errors.push(format!("Invalid due date: {}", due)),
+        }
+    }
+
+    task.title = priority
+        .into_iter()
+        .chain(tokens.iter().map(|token| token.to_string()))
+        .collect::<Vec<_>>()
+        .join(" ");
+    task.modified_at = completed_at.unwrap_or(task.created_at);
+
+    (project_title, task, errors)
+}
+
+// Title of a project from its tag, the reverse of `Project::tag`
+fn project_title(tag: &str) -> String {
+    let mut title = String::new();
+    let mut chars = tag.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => title.extend(chars.next()),
+            '_' => title.push(' '),
+            _ => title.push(char),
+        }
+    }
+
+    title
+}
+
+// Split a leading priority like "(A)" from a title
+fn split_priority(title: &str) -> (Option<&str>, &str) {
+    let bytes = title.as_bytes();
+    if bytes.len() >= 3
+        && bytes[0] == b'('
+        && bytes[1].is_ascii_uppercase()
+        && bytes[2] == b')'
+        && (bytes.len() == 3 || bytes[3] == b' ')
+    {
+        (Some(&title[..3]), title[3..].trim_start())
+    } else {
+        (None, title)
+    }
+}
+
+fn parse_date(date: &str) -> Option<DateTime<Local>> {
+    let date = NaiveDate::parse_from_str(date, DATE_FORMAT).ok()?;
+    Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .earliest()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every line is written like the export writes it
+    const SAMPLE: &str = include_str!("samples/todo.txt");
+    // Lines as they are written by other tools or by hand
+    const VARIANTS: &str = include_str!("samples/todo-variants.txt");
+
+    fn date(date: &str) -> DateTime<Local> {
+        parse_date(date).unwrap()
+    }
+
+    fn project<'a>(imported: &'a Imported, title: &str) -> &'a Project {
+        imported
+            .projects
+            .iter()
+            .find(|project| project.title == title)
+            .unwrap()
+    }
+
+    #[test]
+    fn import_and_export_keep_the_sample() {
+        let imported = import(SAMPLE).unwrap();
+        assert!(imported.errors.is_empty());
+        let projects: Vec<&Project> = imported.projects.iter().collect();
+
+        assert_eq!(export(&projects), SAMPLE);
+    }
+
+    #[test]
+    fn import_parses_the_sample() {
+        let imported = import(SAMPLE).unwrap();
+        let titles: Vec<&str> = imported.projects.iter().map(|p| p.title.as_str()).collect();
+        assert_eq!(titles, ["my_project", "Family", "Weekly review"]);
+
+        let report = &project(&imported, "my_project").tasks[0];
+        assert_eq!(report.title, "(A) Write report @work due:2026-01-09");
+        assert_eq!(report.content, "Contexts: @work\nDue: 2026-01-09\n");
+        assert_eq!(report.created_at, date("2026-01-05"));
+        assert!(!report.done);
+
+        let milk = &project(&imported, "Weekly review").tasks[0];
+        assert!(milk.done);
+        assert_eq!(milk.created_at, date("2026-01-04"));
+        assert_eq!(milk.modified_at, date("2026-01-07"));
+    }
+
+    #[test]
+    fn export_and_import_keep_the_projects() {
+        let mut tasks = Vec::new();
+        for (title, done) in [("(C) Open task @home", false), ("Done task", true)] {
+            let mut task = Task::new(String::from(title));
+            task.done = done;
+            task.created_at = date("2026-02-01");
+            task.modified_at = date(if done { "2026-02-03" } else { "2026-02-01" });
+            tasks.push(task);
+        }
+        let projects: Vec<Project> = ["my_project", "my project", "back\\slash", "Week  2"]
+            .into_iter()
+            .map(|title| Project {
+                tasks: tasks.clone(),
+                ..Project::new(String::from(title))
+            })
+            .collect();
+
+        let imported = import(&export(&projects.iter().collect::<Vec<_>>())).unwrap();
+
+        assert_eq!(imported.projects.len(), projects.len());
+        for (imported, project) in imported.projects.iter().zip(&projects) {
+            assert_eq!(imported.title, project.title);
+            for (imported, task) in imported.tasks.iter().zip(&project.tasks) {
+                assert_eq!(imported.title, task.title);
+                assert_eq!(imported.done, task.done);
+                assert_eq!(imported.created_at, task.created_at);
+                assert_eq!(imported.modified_at, task.modified_at);
+            }
+        }
+    }
+
+    #[test]
+    fn import_parses_the_variants() {
+        let imported = import(VARIANTS).unwrap();
+        assert_eq!(imported.errors, ["Line 6: Invalid due date: someday"]);
+
+        // A single date of a done task is its creation date
+        let inbox = project(&imported, "Inbox");
+        assert_eq!(inbox.tasks[0].title, "Done with a single date");
+        assert!(inbox.tasks[0].done);
+        assert_eq!(inbox.tasks[0].created_at, date("2026-01-03"));
+        assert_eq!(inbox.tasks[0].modified_at, date("2026-01-03"));
+        assert_eq!(inbox.tasks[1].title, "Loose task without a date");
+        assert_eq!(inbox.tasks[2].title, "Done without dates");
+        assert!(inbox.tasks[2].done);
+
+        // The last project tag is the project, the others stay in the title
+        let holidays = project(&imported, "Holidays");
+        assert_eq!(holidays.tasks[0].title, "Plan trip +travel");
+        assert_eq!(holidays.tasks[1].title, "Renew passport due:someday");
+        assert_eq!(holidays.tasks[1].content, "");
+    }
+}
@@ -0,0 +1,475 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use uuid::Uuid;
+
+use crate::application::TodoApp;
+use crate::project::{Project, ProjectContainer};
+use crate::storage::{JsonStorage, Storage};
+use crate::task::Task;
+
+// Optional history of the data file that is kept in a git repository
+//
+// The data directory is the repository and every save creates a commit whose message
+// summarises what changed. The history can be synchronised with a remote repository.
+// Only the JSON data file is supported. If it is encrypted, the commit messages only
+// contain the number of changes but no titles.
+
+const BRANCH: &str = "main";
+const REMOTE: &str = "origin";
+const DATA_FILE: &str = "data.json";
+// Only the data file is tracked, everything else in the data directory is local
+const GITIGNORE: &str = "*\n!.gitignore\n!data.json\n";
+
+pub struct Commit {
+    pub id: String,
+    pub date: String,
+    pub message: String,
+}
+
+// Whether the data directory is a git repository
+pub fn enabled() -> bool {
+    TodoApp::data_directory_path().join(".git").exists()
+}
+
+// Turn the directory of the data file into a git repository and commit the current data
+pub fn init(storage: &mut JsonStorage, remote: Option<&str>) -> Result<(), String> {
+    let directory = repository(storage);
+    if directory.join(".git").exists() {
+        return Err(format!(
+            "History is already enabled in {}",
+            directory.display()
+        ));
+    }
+
+    fs::create_dir_all(directory)
+        .map_err(|error| format!("Could not create data directory: {}", error))?;
+    git(directory, &["init", "--quiet"])?;
+    git(
+        directory,
+        &["symbolic-ref", "HEAD", &format!("refs/heads/{}", BRANCH)],
+    )?;
+    // Commits must not fail because no identity was configured
+    if git(directory, &["config", "user.name"]).is_err() {
+        git(directory, &["config", "user.name", "wetopla"])?;
+    }
+    if git(directory, &["config", "user.email"]).is_err() {
+        git(directory, &["config", "user.email", "wetopla@localhost"])?;
+    }
+    fs::write(directory.join(".gitignore"), GITIGNORE)
+        .map_err(|error| format!("Could not write .gitignore: {}", error))?;
+    git(directory, &["add", ".gitignore"])?;
+    if let Some(remote) = remote {
+        git(directory, &["remote", "add", REMOTE, remote])?;
+    }
+
+    let projects = storage
+        .load()
+        .map_err(|error| format!("Could not read data file: {}", error))?;
+    commit(storage, &projects)
+}
+
+// Commit the data file if it changed
+// `projects` has to be the content of the data file, it is compared with the last commit
+pub fn commit(storage: &JsonStorage, projects: &ProjectContainer) -> Result<(), String> {
+    let directory = repository(storage);
+    if directory.join(DATA_FILE).exists() {
+        git(directory, &["add", DATA_FILE])?;
+    }
+    // Nothing to do if neither the data file nor anything else changed
+    if git(directory, &["status", "--porcelain"])?
+        .trim()
+        .is_empty()
+    {
+        return Ok(());
+    }
+
+    let previous = load(storage, "HEAD").unwrap_or_else(|_| ProjectContainer::new(true));
+    let message = summarize(&previous, projects, !storage.encrypted());
+    git(directory, &["commit", "--quiet", "-m", &message])?;
+
+    Ok(())
+}
+
+// List all commits, the newest one first
+pub fn log() -> Result<Vec<Commit>, String> {
+    let output = git(
+        &TodoApp::data_directory_path(),
+        &[
+            "log",
+            "--format=%h%x09%ad%x09%s",
+            "--date=format:%d.%m.%Y %H:%M",
+        ],
+    )?;
+
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            Some(Commit {
+                id: parts.next()?.to_owned(),
+                date: parts.next()?.to_owned(),
+                message: parts.next()?.to_owned(),
+            })
+        })
+        .collect())
+}
+
+// Read the projects as they were at a commit
+pub fn load(storage: &JsonStorage, commit: &str) -> Result<ProjectContainer, String> {
+    let data = git_output(
+        repository(storage),
+        &["show", &format!("{}:{}", commit, DATA_FILE)],
+    )?;
+    storage
+        .decode(&data)
+        .map_err(|error| format!("Invalid data at {}: {}", commit, error))
+}
+
+// Exchange commits with the remote repository
+// If both sides changed, the projects are merged against the state at the last common
+// commit like an external change of the data file
+// Returns a message that describes what happened
+pub fn sync(storage: &mut JsonStorage) -> Result<String, String> {
+    let directory = repository(storage).to_path_buf();
+    let git = |args: &[&str]| git(&directory, args);
+    let remote_branch = format!("{}/{}", REMOTE, BRANCH);
+    git(&["fetch", "--quiet", REMOTE])?;
+
+    let mut message = String::from("Nothing to synchronise");
+    if git(&["rev-parse", "--verify", "--quiet", &remote_branch]).is_ok() {
+        let behind = git(&["rev-list", "--count", &format!("HEAD..{}", remote_branch)])?;
+        if behind.trim() != "0" {
+            if git(&["merge", "--ff-only", "--quiet", &remote_branch]).is_ok() {
+                message = String::from("Pulled changes");
+            } else {
+                // Both sides changed the single data file, merge the projects instead of lines
+                // A new repository might not contain a data file yet and unrelated histories
+                // have no common commit, everything counts as added then
+                let mut projects =
+                    load(storage, "HEAD").unwrap_or_else(|_| ProjectContainer::new(true));
+                let base = git(&["merge-base", "HEAD", &remote_branch])
+                    .and_then(|commit| load(storage, commit.trim()))
+                    .unwrap_or_else(|_| ProjectContainer::new(true));
+                projects.merge(&base, load(storage, &remote_branch)?);
+                git(&[
+                    "merge",
+                    "--quiet",
+                    "--no-commit",
+                    "--allow-unrelated-histories",
+                    "-s",
+                    "ours",
+                    &remote_branch,
+                ])?;
+                storage
+                    .save(&projects)
+                    .map_err(|error| format!("Could not write data file: {}", error))?;
+                git(&["add", DATA_FILE])?;
+                git(&["commit", "--quiet", "-m", "Merge remote changes"])?;
+                message = String::from("Merged changes");
+            }
+        }
+    }
+
+    let ahead = match git(&["rev-parse", "--verify", "--quiet", &remote_branch]) {
+        Ok(_) => git(&["rev-list", "--count", &format!("{}..HEAD", remote_branch)])?,
+        // The remote does not have the branch yet
+        Err(_) => String::from("1"),
+    };
+    if ahead.trim() != "0" {
+        git(&["push", "--quiet", REMOTE, BRANCH])?;
+        message = if message.starts_with("Nothing") {
+            String::from("Pushed changes")
+        } else {
+            format!("{} and pushed", message)
+        };
+    }
+
+    Ok(message)
+}
+
+// Describe the changes between two states, e.g. "Add 2 tasks, complete 1 task"
+// The body of the message lists every change if `detailed` is set
+fn summarize(previous: &ProjectContainer, current: &ProjectContainer, detailed: bool) -> String {
+    // (verb, noun, details)
+    let mut changes: Vec<(&str, &str, Vec<String>)> = [
+        ("add", "project"),
+        ("remove", "project"),
+        ("rename", "project"),
+        ("add", "task"),
+        ("remove", "task"),
+        ("complete", "task"),
+        ("reopen", "task"),
+        ("rename", "task"),
+        ("move", "task"),
+        ("edit", "task"),
+    ]
+    .into_iter()
+    .map(|(verb, noun)| (verb, noun, Vec::new()))
+    .collect();
+    let mut change = |verb: &str, noun: &str, detail: String| {
+        if let Some((_, _, details)) = changes
+            .iter_mut()
+            .find(|(v, n, _)| *v == verb && *n == noun)
+        {
+            details.push(detail);
+        }
+    };
+
+    let previous_projects: HashMap<_, _> = previous.projects().iter().map(|p| (p.id, p)).collect();
+    let current_projects: HashMap<_, _> = current.projects().iter().map(|p| (p.id, p)).collect();
+    for project in current.projects() {
+        match previous_projects.get(&project.id) {
+            None => change("add", "project", format!("Add project {}", project.title)),
+            Some(old) if old.title != project.title => change(
+                "rename",
+                "project",
+                format!("Rename project {} to {}", old.title, project.title),
+            ),
+            Some(_) => {}
+        }
+    }
+    for project in previous.projects() {
+        if !current_projects.contains_key(&project.id) {
+            change(
+                "remove",
+                "project",
+                format!("Remove project {}", project.title),
+            );
+        }
+    }
+
+    let previous_tasks = tasks_by_id(previous);
+    let current_tasks = tasks_by_id(current);
+    for project in current.projects() {
+        for task in &project.tasks {
+            let Some((old_project, old)) = previous_tasks.get(&task.id) else {
+                change(
+                    "add",
+                    "task",
+                    format!("Add \"{}\" to {}", task.title, project.title),
+                );
+                continue;
+            };
+            if !old.done && task.done {
+                change("complete", "task", format!("Complete \"{}\"", task.title));
+            } else if old.done && !task.done {
+                change("reopen", "task", format!("Reopen \"{}\"", task.title));
+            }
+            if old.title != task.title {
+                change(
+                    "rename",
+                    "task",
+                    format!("Rename \"{}\" to \"{}\"", old.title, task.title),
+                );
+            }
+            if old_project.id != project.id {
+                change(
+                    "move",
+                    "task",
+                    format!("Move \"{}\" to {}", task.title, project.title),
+                );
+            }
+            if old.content != task.content {
+                change("edit", "task", format!("Edit \"{}\"", task.title));
+            }
+        }
+    }
+    for project in previous.projects() {
+        for task in &project.tasks {
+            if !current_tasks.contains_key(&task.id) {
+                change(
+                    "remove",
+                    "task",
+                    format!("Remove \"{}\" from {}", task.title, project.title),
+                );
+            }
+        }
+    }
+
+    let mut subject = Vec::new();
+    let mut body = Vec::new();
+    for (verb, noun, details) in changes.into_iter().filter(|(_, _, d)| !d.is_empty()) {
+        let plural = if details.len() == 1 { "" } else { "s" };
+        subject.push(format!("{} {} {}{}", verb, details.len(), noun, plural));
+        body.extend(details);
+    }
+    if subject.is_empty() {
+        return String::from("Update plan");
+    }
+
+    let subject = subject.join(", ");
+    let mut message = subject[..1].to_uppercase() + &subject[1..];
+    if detailed {
+        message.push_str("\n\n");
+        message.push_str(&body.join("\n"));
+    }
+
+    message
+}
+
+// Map every task id to its project and the task
+fn tasks_by_id(projects: &ProjectContainer) -> HashMap<Uuid, (&Project, &Task)> {
+    projects
+        .projects()
+        .iter()
+        .flat_map(|project| {
+            project
+                .tasks
+                .iter()
+                .map(move |task| (task.id, (project, task)))
+        })
+        .collect()
+}
+
+// The repository is the directory that contains the data file
+fn repository(storage: &JsonStorage) -> &Path {
+    storage.path().parent().unwrap_or(Path::new("."))
+}
+
+// Run git in a repository and return its output
+fn git(directory: &Path, args: &[&str]) -> Result<String, String> {
+    git_output(directory, args).map(|output| String::from_utf8_lossy(&output).into_owned())
+}
+
+// Same as `git` but the output is returned as is since it might be binary
+fn git_output(directory: &Path, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(args)
+        // Never wait for credentials since the TUI owns the terminal
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .map_err(|error| format!("Could not run git: {}", error))?;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    // Temporary directory that is removed again at the end of a test
+    struct TempDirectory(PathBuf);
+
+    impl TempDirectory {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("wetopla-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDirectory(path)
+        }
+    }
+
+    impl Drop for TempDirectory {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn storage(directory: &Path) -> JsonStorage {
+        JsonStorage::new(directory.join(DATA_FILE), None)
+    }
+
+    // Save the projects and commit them like the application does
+    fn save(storage: &mut JsonStorage, projects: &ProjectContainer) {
+        storage.save(projects).unwrap();
+        commit(storage, projects).unwrap();
+    }
+
+    fn titles(projects: &ProjectContainer) -> Vec<(String, bool)> {
+        projects.projects()[0]
+            .tasks
+            .iter()
+            .map(|task| (task.title.clone(), task.done))
+            .collect()
+    }
+
+    #[test]
+    fn sync_pushes_pulls_and_merges_diverged_histories() {
+        let temp = TempDirectory::new("sync");
+        let remote = temp.0.join("remote.git");
+        let ours_directory = temp.0.join("ours");
+        let theirs_directory = temp.0.join("theirs");
+        git(&temp.0, &["init", "--quiet", "--bare", "remote.git"]).unwrap();
+
+        let mut project = Project::new(String::from("Week"));
+        for title in ["Kept", "Completed", "Renamed", "Removed"] {
+            project.add_task(String::from(title));
+        }
+        let mut projects = ProjectContainer::with_projects(vec![project], 0, true);
+        let mut ours = storage(&ours_directory);
+        fs::create_dir_all(&ours_directory).unwrap();
+        ours.save(&projects).unwrap();
+        init(&mut ours, Some(remote.to_str().unwrap())).unwrap();
+        assert_eq!(sync(&mut ours).unwrap(), "Pushed changes");
+
+        git(
+            &temp.0,
+            &[
+                "clone",
+                "--quiet",
+                "--branch",
+                BRANCH,
+                "remote.git",
+                "theirs",
+            ],
+        )
+        .unwrap();
+        git(&theirs_directory, &["config", "user.name", "wetopla"]).unwrap();
+        git(
+            &theirs_directory,
+            &["config", "user.email", "wetopla@localhost"],
+        )
+        .unwrap();
+        let mut theirs = storage(&theirs_directory);
+        assert_eq!(sync(&mut theirs).unwrap(), "Nothing to synchronise");
+
+        // One side changes, the other one only pulls
+        projects
+            .project_mut(0)
+            .unwrap()
+            .add_task(String::from("Added"));
+        save(&mut ours, &projects);
+        assert_eq!(sync(&mut ours).unwrap(), "Pushed changes");
+        assert_eq!(sync(&mut theirs).unwrap(), "Pulled changes");
+        assert_eq!(titles(&theirs.load().unwrap()), titles(&projects));
+
+        // Both sides change different tasks
+        let mut their_projects = theirs.load().unwrap();
+        let their_project = their_projects.project_mut(0).unwrap();
+        their_project.toggle_task_done(1);
+        their_project.remove_selected_task(3);
+        save(&mut theirs, &their_projects);
+        let our_project = projects.project_mut(0).unwrap();
+        our_project.rename_task(2, String::from("Renamed by us"));
+        save(&mut ours, &projects);
+
+        assert_eq!(sync(&mut theirs).unwrap(), "Pushed changes");
+        assert_eq!(sync(&mut ours).unwrap(), "Merged changes and pushed");
+        assert_eq!(sync(&mut theirs).unwrap(), "Pulled changes");
+
+        let merged = vec![
+            (String::from("Kept"), false),
+            (String::from("Completed"), true),
+            (String::from("Renamed by us"), false),
+            (String::from("Added"), false),
+        ];
+        assert_eq!(titles(&ours.load().unwrap()), merged);
+        assert_eq!(titles(&theirs.load().unwrap()), merged);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::utils::Rect;
+
+// Layout of widgets
+//
+// An area is split into parts along one direction. The size of every part is described by a
+// constraint. Fixed and percentage sizes are assigned first, the remaining space is shared by
+// the parts with a minimum or maximum size. If the area is too small, the last parts shrink.
+
+#[derive(Clone, Copy)]
+pub enum Constraint {
+    // Exactly this many cells
+    Fixed(u16),
+    // This share of the whole area
+    Percentage(u16),
+    // At least this many cells and as much of the remaining space as possible
+    Min(u16),
+    // As much of the remaining space as possible but not more than this many cells
+    Max(u16),
+}
+
+#[derive(Clone, Copy)]
+pub enum Direction {
+    // Parts are placed next to each other
+    Horizontal,
+    // Parts are placed below each other
+    Vertical,
+}
+
+// Split an area into one part for every constraint
+pub fn split<const N: usize>(
+    area: &Rect,
+    direction: Direction,
+    constraints: [Constraint; N],
+) -> [Rect; N] {
+    let total = match direction {
+        Direction::Horizontal => area.width,
+        Direction::Vertical => area.height,
+    };
+
+    let mut sizes = constraints.map(|constraint| match constraint {
+        Constraint::Fixed(size) | Constraint::Min(size) => size,
+        Constraint::Percentage(percentage) => percentage_of(total, percentage),
+        Constraint::Max(_) => 0,
+    });
+
+    // Shrink the last parts until everything fits
+    let mut overflow = sum(&sizes).saturating_sub(total as u32);
+    for size in sizes.iter_mut().rev() {
+        let shrink = overflow.min(*size as u32) as u16;
+        *size -= shrink;
+        overflow -= shrink as u32;
+    }
+
+    // Share the remaining space between the flexible parts
+    let mut remaining = total - sum(&sizes) as u16;
+    loop {
+        let growable: Vec<usize> = constraints
+            .iter()
+            .enumerate()
+            .filter(|(i, constraint)| match constraint {
+                Constraint::Min(_) => true,
+                Constraint::Max(max) => sizes[*i] < *max,
+                _ => false,
+            })
+            .map(|(i, _)| i)
+            .collect();
+        if remaining == 0 || growable.is_empty() {
+            break;
+        }
+
+        // The first parts get one cell more if the space cannot be shared evenly
+        let share = remaining / growable.len() as u16;
+        let mut extra = remaining % growable.len() as u16;
+        for i in growable {
+            let mut grow = share;
+            if extra > 0 {
+                grow += 1;
+                extra -= 1;
+            }
+            if let Constraint::Max(max) = constraints[i] {
+                grow = grow.min(max - sizes[i]);
+            }
+            sizes[i] += grow;
+            remaining -= grow;
+        }
+    }
+
+    let mut offset = 0;
+    sizes.map(|size| {
+        let part = match direction {
+            Direction::Horizontal => Rect {
+                x: area.x + offset,
+                y: area.y,
+                width: size,
+                height: area.height,
+            },
+            Direction::Vertical => Rect {
+                x: area.x,
+                y: area.y + offset,
+                width: area.width,
+                height: size,
+            },
+        };
+        offset += size;
+        part
+    })
+}
+
+// Area in the centre of another area, it never exceeds the other area
+pub fn centered(area: &Rect, width: Constraint, height: Constraint) -> Rect {
+    let width = clamp(width, area.width);
+    let height = clamp(height, area.height);
+
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+// Size of a single part that has the whole space available
+fn clamp(constraint: Constraint, total: u16) -> u16 {
+    match constraint {
+        Constraint::Fixed(size) | Constraint::Max(size) => size.min(total),
+        Constraint::Percentage(percentage) => percentage_of(total, percentage),
+        Constraint::Min(_) => total,
+    }
+}
+
+fn sum(sizes: &[u16]) -> u32 {
+    sizes.iter().map(|size| *size as u32).sum()
+}
+
+fn percentage_of(total: u16, percentage: u16) -> u16 {
+    (total as u32 * percentage.min(100) as u32 / 100) as u16
+}
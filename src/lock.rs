@@ -0,0 +1,121 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+// Advisory lock that prevents multiple instances from writing the same data file
+//
+// The lock file contains "<pid>@<host>" of the instance that holds the lock. A lock
+// that was left behind by a crashed instance on the same host is taken over.
+pub struct Lock {
+    path: PathBuf,
+}
+
+impl Lock {
+    // Try to acquire the lock
+    // Returns an error that names the owner if the lock is held by another instance
+    pub fn acquire(path: &Path) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|error| format!("Could not create data directory: {}", error))?;
+        }
+
+        // The lock file is created as a link to a complete file, so other instances never
+        // see an empty lock file that looks stale
+        let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&temporary, owner())
+            .map_err(|error| format!("Could not write lock file: {}", error))?;
+        let lock = Self::create(path, &temporary);
+        let _ = fs::remove_file(&temporary);
+
+        lock
+    }
+
+    fn create(path: &Path, temporary: &Path) -> Result<Self, String> {
+        // Retry once after removing a stale lock
+        for _ in 0..2 {
+            match fs::hard_link(temporary, path) {
+                Ok(()) => {
+                    return Ok(Self {
+                        path: path.to_path_buf(),
+                    });
+                }
+                Err(error) if error.kind() == ErrorKind::AlreadyExists => remove_stale(path)?,
+                Err(error) => return Err(format!("Could not create lock file: {}", error)),
+            }
+        }
+
+        Err(String::from("Could not acquire lock"))
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        // Only remove the lock file if it still belongs to this instance
+        if fs::read_to_string(&self.path).is_ok_and(|content| content.trim() == owner()) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|name| name.trim().to_owned())
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| String::from("localhost"))
+}
+
+fn owner() -> String {
+    format!("{}@{}", std::process::id(), hostname())
+}
+
+// Remove the lock file if its owner does not exist anymore
+// Instances that take over a lock at the same time could remove the lock that another one
+// just created. So only one instance at a time checks and removes it, the others wait for
+// the takeover lock and see the new owner afterwards.
+fn remove_stale(path: &Path) -> Result<(), String> {
+    // Released when the file is closed at the end
+    let _takeover = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path.with_extension("takeover"))
+        .and_then(|file| file.lock().map(|_| file))
+        .map_err(|error| format!("Could not lock the lock file: {}", error))?;
+
+    let current_owner = match fs::read_to_string(path) {
+        Ok(owner) => owner,
+        // The owner released the lock in the meantime
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(_) => String::new(),
+    };
+    if !is_stale(current_owner.trim()) {
+        return Err(format!("Data is locked by {}", current_owner.trim()));
+    }
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != ErrorKind::NotFound => {
+            Err(format!("Could not remove stale lock file: {}", error))
+        }
+        _ => Ok(()),
+    }
+}
+
+// Whether the owner of a lock does not exist anymore
+// Locks of other hosts are never considered stale since we cannot check them
+fn is_stale(owner: &str) -> bool {
+    match owner.split_once('@') {
+        Some((pid, host)) if host == hostname() => match pid.parse::<libc::pid_t>() {
+            Ok(pid) if pid > 0 => !process_exists(pid),
+            _ => true,
+        },
+        Some(_) => false,
+        // The lock file is empty or was written by something else
+        None => true,
+    }
+}
+
+fn process_exists(pid: libc::pid_t) -> bool {
+    // Signal 0 is never sent, only the existence of the process is checked
+    // The process exists but belongs to another user if sending is not permitted
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
@@ -1,13 +1,23 @@
 mod application;
 mod buffer;
+mod cli;
+mod crypto;
 mod event_loop;
+mod formats;
+mod history;
+mod layout;
+mod lock;
 mod project;
+mod state;
+mod storage;
 mod task;
 mod terminal;
 mod utils;
 mod widgets;
+mod workspace;
 
 use application::TodoApp;
+use cli::Command;
 
 fn set_panic_hook() {
     let hook = std::panic::take_hook();
@@ -19,10 +29,32 @@ fn set_panic_hook() {
 
 #[tokio::main]
 async fn main() -> Result<(), std::io::Error> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    // Only enter the TUI if no subcommand was given
+    match cli::select_workspace(&mut args)
+        .and_then(|_| Command::parse(&args))
+        .and_then(|command| command.map(Command::run).transpose())
+    {
+        Ok(Some(())) => return Ok(()),
+        Ok(None) => {}
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
+
+    // Open the same workspace the next time
+    if let Err(error) = workspace::remember(&workspace::current()) {
+        eprintln!("{}", error);
+    }
+    let storage = storage::open(None)?;
     set_panic_hook();
     terminal::prepare_terminal()?;
-    let mut app = TodoApp::new();
-    app.import_projects();
+    let mut app = TodoApp::new(storage);
+    // The data of an encrypted file is loaded after the passphrase was entered
+    if !app.locked() {
+        app.import_projects();
+    }
     app.run().await?;
     terminal::restore_terminal()?;
 
@@ -1,16 +1,26 @@
 use crate::application::TodoApp;
 use crate::buffer::Buffer;
 use crate::task::Task;
-use crate::utils::{Rect, border, build_row};
+use crate::utils::{Rect, border, build_row, id_matches};
 use crate::widgets::{ContainerWidget, Widget};
 use chrono::Local;
 use crossterm::style::Stylize;
 use serde::{Deserialize, Serialize};
-use std::{fs, process::Command};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use uuid::Uuid;
 
 // A project contains a list of tasks
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Project {
+    // Data files written before ids were introduced don't contain an id
+    // A nil id is replaced by a generated one after loading
+    #[serde(default)]
+    pub id: Uuid,
     pub title: String,
     pub tasks: Vec<Task>,
     pub done: bool,
@@ -24,14 +34,21 @@ pub struct ProjectContainer {
 }
 
 impl Project {
-    pub fn add_task(&mut self, task_title: String) {
-        self.tasks.push(Task {
-            title: task_title,
-            content: String::new(),
-            created_at: Local::now(),
-            modified_at: Local::now(),
+    pub fn new(title: String) -> Self {
+        Project {
+            id: Uuid::new_v4(),
+            title,
+            tasks: Vec::new(),
             done: false,
-        });
+        }
+    }
+
+    pub fn add_task(&mut self, task_title: String) -> Uuid {
+        let task = Task::new(task_title);
+        let id = task.id;
+        self.tasks.push(task);
+
+        id
     }
 
     pub fn remove_selected_task(&mut self, task_index: usize) {
@@ -40,8 +57,21 @@ impl Project {
         }
     }
 
-    pub fn edit_task(&mut self, task_index: usize) -> bool {
-        let tmp_file = TodoApp::data_directory_path().join("task.edit");
+    // `secure` keeps the content away from persistent storage as far as possible
+    // This is used if the data file is encrypted, the task is not edited if there is no
+    // directory in memory
+    // Returns whether the content of the task was changed
+    pub fn edit_task(&mut self, task_index: usize, secure: bool) -> bool {
+        let directory = if secure {
+            match memory_directory() {
+                Some(directory) => directory,
+                None => return false,
+            }
+        } else {
+            TodoApp::data_directory_path()
+        };
+        // Use a unique file so that multiple instances don't edit the same file
+        let tmp_file = EditFile(directory.join(format!("task-{}.edit", Uuid::new_v4())));
 
         if self.tasks.is_empty() {
             // Don't do anything if there are not tasks
@@ -50,31 +80,112 @@ impl Project {
         // Get task that we want to edit
         let task_to_edit = self.tasks.get_mut(task_index).unwrap();
 
-        if !task_to_edit.content.is_empty() {
+        let mut editor = Command::new("vim");
+        if secure {
+            // Only the user may read the file, even if it is empty, since vim keeps the mode
+            OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .mode(0o600)
+                .open(&tmp_file.0)
+                .and_then(|mut file| file.write_all(task_to_edit.content.as_bytes()))
+                .expect("Unexpected error when trying to write to the file that is used for editing");
+            // Don't write swap, undo, backup and viminfo files that contain the content
+            editor.args(["-n", "-i", "NONE", "--cmd", "set noundofile nobackup nowritebackup"]);
+        } else if !task_to_edit.content.is_empty() {
             // Prefill edit file with the already existing content
-            fs::write(&tmp_file, &task_to_edit.content).expect(
+            fs::write(&tmp_file.0, &task_to_edit.content).expect(
                 "Unexpected error when trying to write to the file that is used for editing",
             );
         }
 
-        Command::new("vim")
-            .arg(&tmp_file)
+        editor
+            .arg(&tmp_file.0)
             .status()
             .expect("Could not find editor! Please install vim!");
 
         // Actually edit the content of the current task
-        if let Ok(content) = fs::read_to_string(&tmp_file) {
+        if let Ok(content) = fs::read_to_string(&tmp_file.0)
+            && task_to_edit.content != content
+        {
             task_to_edit.content = content;
-            fs::remove_file(tmp_file)
-                .expect("Unexpected error when trying to delete file that is used for editing");
+            task_to_edit.modified_at = Local::now();
+            return true;
+        }
+
+        false
+    }
+
+    // Title that can be used as a single word, e.g. for a todo.txt project tag
+    // Spaces become underscores, so underscores and backslashes are escaped with a backslash
+    pub fn tag(&self) -> String {
+        let mut tag = String::new();
+        for char in self.title.trim().chars() {
+            match char {
+                '\\' | '_' => {
+                    tag.push('\\');
+                    tag.push(char);
+                }
+                char if char.is_whitespace() => tag.push('_'),
+                char => tag.push(char),
+            }
         }
 
-        true
+        tag
     }
 
     pub fn toggle_task_done(&mut self, task_index: usize) {
         let task = self.tasks.get_mut(task_index).unwrap();
         task.done = !task.done;
+        task.modified_at = Local::now();
+    }
+
+    pub fn rename_task(&mut self, task_index: usize, title: String) {
+        let task = self.tasks.get_mut(task_index).unwrap();
+        task.title = title;
+        task.modified_at = Local::now();
+    }
+}
+
+// Map every task id to the id of its project and the task
+fn locate_tasks(projects: &ProjectContainer) -> HashMap<Uuid, (Uuid, Task)> {
+    projects
+        .projects
+        .iter()
+        .flat_map(|project| {
+            project
+                .tasks
+                .iter()
+                .map(|task| (task.id, (project.id, task.clone())))
+        })
+        .collect()
+}
+
+// Take the side that changed a value, `prefer_ours` decides if both sides changed it
+fn merge_value<T: Clone + PartialEq>(base: &T, ours: &T, theirs: &T, prefer_ours: bool) -> T {
+    if *ours == *base || (*theirs != *base && !prefer_ours) {
+        theirs.clone()
+    } else {
+        ours.clone()
+    }
+}
+
+// Directory that is kept in memory instead of on disk, if there is one
+pub fn memory_directory() -> Option<PathBuf> {
+    let runtime_directory = std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from);
+    [runtime_directory, Some(PathBuf::from("/dev/shm"))]
+        .into_iter()
+        .flatten()
+        .find(|directory| directory.is_dir())
+}
+
+// File that is used for editing the content of a task
+// It is removed when editing is done, even if the editor could not be started
+struct EditFile(PathBuf);
+
+impl Drop for EditFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
     }
 }
 
@@ -87,13 +198,22 @@ impl ProjectContainer {
         }
     }
 
+    // Create a container from projects that were read from a storage
+    pub fn with_projects(projects: Vec<Project>, selected: usize, focused: bool) -> Self {
+        ProjectContainer {
+            projects,
+            selected,
+            focused,
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
     pub fn add_project(&mut self, project_name: String) {
         if !project_name.is_empty() {
-            self.projects.push(Project {
-                title: project_name,
-                tasks: Vec::new(),
-                done: false,
-            });
+            self.projects.push(Project::new(project_name));
             self.selected = self.projects.len() - 1;
         }
     }
@@ -114,6 +234,215 @@ impl ProjectContainer {
     pub fn current_project(&mut self) -> Option<&mut Project> {
         self.projects.get_mut(self.selected)
     }
+
+    pub fn projects(&self) -> &[Project] {
+        &self.projects
+    }
+
+    pub fn project_mut(&mut self, project_index: usize) -> Option<&mut Project> {
+        self.projects.get_mut(project_index)
+    }
+
+    // Find projects by their title or, if no title matches, by an id prefix
+    pub fn find_project(&self, name: &str) -> Vec<usize> {
+        let by_title: Vec<usize> = (0..self.projects.len())
+            .filter(|i| self.projects[*i].title == name)
+            .collect();
+        if !by_title.is_empty() {
+            return by_title;
+        }
+
+        (0..self.projects.len())
+            .filter(|i| id_matches(&self.projects[*i].id, name))
+            .collect()
+    }
+
+    // Find tasks by an id prefix
+    // Returns the project index and the task index of every match
+    pub fn find_task(&self, id: &str) -> Vec<(usize, usize)> {
+        let mut matches = Vec::new();
+        for (project_index, project) in self.projects.iter().enumerate() {
+            for (task_index, task) in project.tasks.iter().enumerate() {
+                if id_matches(&task.id, id) {
+                    matches.push((project_index, task_index));
+                }
+            }
+        }
+
+        matches
+    }
+
+    // Move a task to the end of another project
+    pub fn move_task(&mut self, project_index: usize, task_index: usize, target_index: usize) {
+        let task = self.projects[project_index].tasks.remove(task_index);
+        self.projects[target_index].tasks.push(task);
+    }
+
+    // Select the project with the given id if it exists
+    pub fn select_project(&mut self, id: &Uuid) {
+        if let Some(index) = self.projects.iter().position(|project| project.id == *id) {
+            self.selected = index;
+        }
+    }
+
+    // Three-way merge of projects that were changed by someone else into the current ones
+    // `base` is the state that both sides started from. Projects and tasks are matched by their
+    // id, tasks also across projects. A change that only one side made is taken over, if both
+    // sides changed a task the one that was modified last is kept. Something that was deleted on
+    // one side stays deleted unless the other side changed it.
+    pub fn merge(&mut self, base: &ProjectContainer, other: ProjectContainer) {
+        let base_tasks = locate_tasks(base);
+        let our_tasks = locate_tasks(self);
+        let their_tasks = locate_tasks(&other);
+
+        // Merged tasks with the id of their project, in the order they appear on our side,
+        // their side and in the base
+        let mut tasks: Vec<(Uuid, Task)> = Vec::new();
+        let mut seen = HashSet::new();
+        let all_tasks = [&*self, &other, base]
+            .into_iter()
+            .flat_map(|container| &container.projects)
+            .flat_map(|project| &project.tasks);
+        for id in all_tasks.map(|task| task.id) {
+            if !seen.insert(id) {
+                continue;
+            }
+            let merged = match (
+                base_tasks.get(&id),
+                our_tasks.get(&id),
+                their_tasks.get(&id),
+            ) {
+                (Some(base), Some(ours), Some(theirs)) => {
+                    let ours_newer = ours.1.modified_at >= theirs.1.modified_at;
+                    Some((
+                        merge_value(&base.0, &ours.0, &theirs.0, true),
+                        merge_value(&base.1, &ours.1, &theirs.1, ours_newer),
+                    ))
+                }
+                (Some(base), Some(kept), None) | (Some(base), None, Some(kept)) => {
+                    (kept != base).then(|| kept.clone())
+                }
+                (None, Some(ours), Some(theirs)) => {
+                    if theirs.1.modified_at > ours.1.modified_at {
+                        Some(theirs.clone())
+                    } else {
+                        Some(ours.clone())
+                    }
+                }
+                (None, Some(added), None) | (None, None, Some(added)) => Some(added.clone()),
+                _ => None,
+            };
+            tasks.extend(merged);
+        }
+
+        let base_projects: HashMap<_, _> = base.projects.iter().map(|p| (p.id, p)).collect();
+        let our_projects: HashMap<_, _> = self.projects.iter().map(|p| (p.id, p)).collect();
+        let their_projects: HashMap<_, _> = other.projects.iter().map(|p| (p.id, p)).collect();
+        let mut projects = Vec::new();
+        let mut seen = HashSet::new();
+        let all_projects = self
+            .projects
+            .iter()
+            .chain(&other.projects)
+            .chain(&base.projects);
+        for id in all_projects.map(|project| project.id) {
+            if !seen.insert(id) {
+                continue;
+            }
+            // A deleted project is kept if tasks were added to it or changed in it
+            let has_tasks = tasks.iter().any(|(project_id, _)| *project_id == id);
+            let merged = match (
+                base_projects.get(&id),
+                our_projects.get(&id),
+                their_projects.get(&id),
+            ) {
+                (Some(base), Some(ours), Some(theirs)) => Some(Project {
+                    title: merge_value(&base.title, &ours.title, &theirs.title, true),
+                    done: merge_value(&base.done, &ours.done, &theirs.done, true),
+                    ..(*ours).clone()
+                }),
+                (Some(base), Some(kept), None) | (Some(base), None, Some(kept)) => {
+                    let changed = kept.title != base.title || kept.done != base.done;
+                    (has_tasks || changed).then(|| (*kept).clone())
+                }
+                (Some(base), None, None) => has_tasks.then(|| (*base).clone()),
+                (None, Some(added), _) | (None, None, Some(added)) => Some((*added).clone()),
+                (None, None, None) => None,
+            };
+            if let Some(mut project) = merged {
+                project.tasks = tasks
+                    .iter()
+                    .filter(|(project_id, _)| *project_id == id)
+                    .map(|(_, task)| task.clone())
+                    .collect();
+                projects.push(project);
+            }
+        }
+
+        self.projects = projects;
+        self.selected = self.selected.min(self.projects.len().saturating_sub(1));
+    }
+
+    // Add imported projects
+    // Tasks are added to the target project if one is given, otherwise to the
+    // project with the same title which is created if it does not exist yet
+    // Tasks whose id already exists are skipped, a new project is only created for
+    // an empty project or if at least one of its tasks is added
+    // Returns the number of tasks that were added
+    pub fn import(&mut self, projects: Vec<Project>, target_index: Option<usize>) -> usize {
+        let mut imported = 0;
+
+        for project in projects {
+            let mut project_index = target_index
+                .or_else(|| self.projects.iter().position(|p| p.title == project.title));
+            let new_project = Project {
+                tasks: Vec::new(),
+                ..project.clone()
+            };
+            if project_index.is_none() && project.tasks.is_empty() {
+                self.projects.push(new_project.clone());
+                project_index = Some(self.projects.len() - 1);
+            }
+
+            for task in project.tasks {
+                let exists = self
+                    .projects
+                    .iter()
+                    .any(|p| p.tasks.iter().any(|t| t.id == task.id));
+                if !exists {
+                    let index = *project_index.get_or_insert_with(|| {
+                        self.projects.push(new_project.clone());
+                        self.projects.len() - 1
+                    });
+                    self.projects[index].tasks.push(task);
+                    imported += 1;
+                }
+            }
+        }
+
+        imported
+    }
+
+    // Give every project and task that was loaded without an id one
+    // The ids are derived from the path and content of the data file and the position, so they
+    // stay the same every time the data is loaded until it is saved with them, but two
+    // different data files never get the same ids
+    pub fn assign_missing_ids(&mut self, path: &Path, data: &[u8]) {
+        let file = Uuid::new_v5(&Uuid::NAMESPACE_URL, path.to_string_lossy().as_bytes());
+        let namespace = Uuid::new_v5(&file, data);
+        for (project_index, project) in self.projects.iter_mut().enumerate() {
+            if project.id.is_nil() {
+                let name = format!("project/{}", project_index);
+                project.id = Uuid::new_v5(&namespace, name.as_bytes());
+            }
+            for (task_index, task) in project.tasks.iter_mut().enumerate() {
+                if task.id.is_nil() {
+                    let name = format!("task/{}/{}", project_index, task_index);
+                    task.id = Uuid::new_v5(&namespace, name.as_bytes());
+                }
+            }
+        }
+    }
 }
 
 impl ContainerWidget for ProjectContainer {
@@ -144,6 +473,24 @@ impl ContainerWidget for ProjectContainer {
     fn set_focus(&mut self, focus: bool) {
         self.focused = focus;
     }
+
+    fn select(&mut self, index: usize) {
+        if index < self.projects.len() {
+            self.selected = index;
+        }
+    }
+
+    fn item_at(&self, available_area: &Rect, column: u16, row: u16) -> Option<usize> {
+        let area = self.rect(available_area);
+        // Projects are listed inside the border, one per row
+        if column <= area.x || column + 1 >= area.x + area.width || row <= area.y {
+            return None;
+        }
+        let index = (row - area.y - 1) as usize;
+        let rows = (area.height as usize).saturating_sub(2);
+
+        (index < rows && index < self.projects.len()).then_some(index)
+    }
 }
 
 impl Widget for ProjectContainer {
@@ -161,17 +508,12 @@ impl Widget for ProjectContainer {
             None,
             None,
         );
-        for (i, project) in self.projects.iter().enumerate() {
+        let space = (area.width as usize).saturating_sub(2);
+        let rows = (area.height as usize).saturating_sub(2);
+        for (i, project) in self.projects.iter().enumerate().take(rows) {
             let mut styled_project = build_row(vec![
-                (&format!("{}: ", i), 3),
-                (
-                    if project.title.len() > area.width as usize - 5 {
-                        &project.title[..area.width as usize - 5]
-                    } else {
-                        &project.title
-                    },
-                    available_area.width as usize - 5
-                )
+                (&format!("{}: ", i), space.min(3)),
+                (&project.title, space.saturating_sub(3))
             ])
             .white();
 
@@ -182,3 +524,211 @@ impl Widget for ProjectContainer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(title: &str, tasks: &[&Task]) -> Project {
+        Project {
+            tasks: tasks.iter().map(|task| (*task).clone()).collect(),
+            ..Project::new(title.to_owned())
+        }
+    }
+
+    fn container(projects: &[&Project]) -> ProjectContainer {
+        ProjectContainer::with_projects(projects.iter().map(|p| (*p).clone()).collect(), 0, true)
+    }
+
+    // Titles of the tasks of every project
+    fn titles(projects: &ProjectContainer) -> Vec<(String, Vec<String>)> {
+        projects
+            .projects()
+            .iter()
+            .map(|p| {
+                (
+                    p.title.clone(),
+                    p.tasks.iter().map(|t| t.title.clone()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn expected(projects: &[(&str, &[&str])]) -> Vec<(String, Vec<String>)> {
+        projects
+            .iter()
+            .map(|(title, tasks)| {
+                (
+                    title.to_string(),
+                    tasks.iter().map(|t| t.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn merge_keeps_a_moved_task_once() {
+        let task = Task::new(String::from("Moved"));
+        let base = container(&[&project("A", &[&task]), &project("B", &[])]);
+        let mut ours = base.clone();
+        let mut theirs = base.clone();
+        theirs.move_task(0, 0, 1);
+
+        ours.merge(&base, theirs);
+
+        assert_eq!(titles(&ours), expected(&[("A", &[]), ("B", &["Moved"])]));
+    }
+
+    #[test]
+    fn merge_keeps_deletions_of_both_sides() {
+        let kept = Task::new(String::from("Kept"));
+        let ours_deleted = Task::new(String::from("Deleted by us"));
+        let theirs_deleted = Task::new(String::from("Deleted by them"));
+        let a = project("A", &[&kept, &ours_deleted, &theirs_deleted]);
+        let b = project("B", &[]);
+        let base = container(&[&a, &b]);
+        let mut ours = container(&[
+            &Project {
+                tasks: vec![kept.clone(), theirs_deleted.clone()],
+                ..a.clone()
+            },
+            &b,
+        ]);
+        let theirs = container(&[&Project {
+            tasks: vec![kept.clone(), ours_deleted],
+            ..a.clone()
+        }]);
+
+        ours.merge(&base, theirs);
+
+        assert_eq!(titles(&ours), expected(&[("A", &["Kept"])]));
+    }
+
+    #[test]
+    fn merge_keeps_changes_to_deleted_tasks() {
+        let task = Task::new(String::from("Task"));
+        let a = project("A", &[&task]);
+        let base = container(&[&a]);
+        let mut edited = task.clone();
+        edited.title = String::from("Edited");
+        edited.modified_at = task.modified_at + chrono::Duration::seconds(1);
+        let mut ours = container(&[&Project {
+            tasks: vec![edited],
+            ..a.clone()
+        }]);
+        let theirs = container(&[&Project {
+            tasks: Vec::new(),
+            ..a.clone()
+        }]);
+
+        ours.merge(&base, theirs);
+
+        assert_eq!(titles(&ours), expected(&[("A", &["Edited"])]));
+    }
+
+    #[test]
+    fn merge_takes_changes_of_both_sides() {
+        let first = Task::new(String::from("First"));
+        let second = Task::new(String::from("Second"));
+        let a = project("A", &[&first, &second]);
+        let base = container(&[&a]);
+        let mut ours = base.clone();
+        ours.project_mut(0).unwrap().tasks[0].title = String::from("First renamed");
+        let mut theirs = base.clone();
+        theirs.project_mut(0).unwrap().tasks[1].done = true;
+        theirs.project_mut(0).unwrap().title = String::from("A renamed");
+
+        ours.merge(&base, theirs);
+
+        assert_eq!(
+            titles(&ours),
+            expected(&[("A renamed", &["First renamed", "Second"])])
+        );
+        assert!(ours.projects()[0].tasks[1].done);
+    }
+
+    // Ids of every project and task
+    fn ids(projects: &ProjectContainer) -> Vec<Uuid> {
+        projects
+            .projects()
+            .iter()
+            .flat_map(|p| std::iter::once(p.id).chain(p.tasks.iter().map(|t| t.id)))
+            .collect()
+    }
+
+    // Projects as they are read from a data file that was written before ids were introduced
+    fn without_ids(titles: &[(&str, &[&str])]) -> ProjectContainer {
+        let projects: Vec<Project> = titles
+            .iter()
+            .map(|(title, tasks)| Project {
+                id: Uuid::nil(),
+                tasks: tasks
+                    .iter()
+                    .map(|title| Task {
+                        id: Uuid::nil(),
+                        ..Task::new(title.to_string())
+                    })
+                    .collect(),
+                ..Project::new(title.to_string())
+            })
+            .collect();
+        container(&projects.iter().collect::<Vec<_>>())
+    }
+
+    // Assign ids as if the projects were loaded from a data file at this path
+    fn load(projects: &ProjectContainer, path: &str) -> ProjectContainer {
+        let data = serde_json::to_vec(projects).unwrap();
+        let mut loaded: ProjectContainer = serde_json::from_slice(&data).unwrap();
+        loaded.assign_missing_ids(Path::new(path), &data);
+        loaded
+    }
+
+    #[test]
+    fn missing_ids_differ_between_data_files() {
+        let first = without_ids(&[("Inbox", &["Dentist", "Taxes"])]);
+        let second = without_ids(&[("Inbox", &["Groceries", "Taxes"]), ("Work", &["Report"])]);
+
+        let first_ids = ids(&load(&first, "/home/user/.weeklyplaner/data.json"));
+        assert!(first_ids.iter().all(|id| !id.is_nil()));
+        let other_ids = [
+            // Another data file
+            ids(&load(&second, "/home/user/.weeklyplaner/data.json")),
+            // The same data in another workspace
+            ids(&load(&first, "/home/user/.weeklyplaner/work/data.json")),
+        ];
+        for other_ids in other_ids {
+            assert!(other_ids.iter().all(|id| !first_ids.contains(id)));
+        }
+    }
+
+    #[test]
+    fn missing_ids_stay_the_same_until_saved() {
+        let projects = without_ids(&[("Inbox", &["Dentist", "Taxes"])]);
+
+        assert_eq!(
+            ids(&load(&projects, "data.json")),
+            ids(&load(&projects, "data.json"))
+        );
+    }
+
+    #[test]
+    fn import_skips_projects_without_new_tasks() {
+        let task = Task::new(String::from("Existing"));
+        let mut projects = container(&[&project("A", &[&task])]);
+
+        let count = projects.import(
+            vec![
+                project("B", &[&task]),
+                project("C", &[&Task::new(String::from("New"))]),
+                project("D", &[]),
+            ],
+            None,
+        );
+
+        assert_eq!(count, 1);
+        assert_eq!(
+            titles(&projects),
+            expected(&[("A", &["Existing"]), ("C", &["New"]), ("D", &[])])
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::workspace;
+
+// Number of values that are remembered for every kind of line input
+const INPUT_HISTORY_LENGTH: usize = 100;
+
+// Settings that are remembered between runs
+// They are shared by all workspaces and stored next to the data of the default workspace
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+    // Workspace that was opened last
+    pub workspace: Option<String>,
+    pub panes: Panes,
+}
+
+// Sizes of the panes in percent, a pane is collapsed if it gets no space
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Panes {
+    // Width of the project pane, the task pane gets the rest
+    pub projects: u16,
+    // Height of the task list, the task content gets the rest
+    pub task_list: u16,
+}
+
+impl Default for Panes {
+    fn default() -> Self {
+        Panes {
+            projects: 20,
+            task_list: 50,
+        }
+    }
+}
+
+// Kinds of values that are entered in a line input, every kind has its own history
+#[derive(Clone, Copy, PartialEq)]
+pub enum InputKind {
+    Project,
+    Task,
+}
+
+// Values that were entered in line inputs, the newest last
+// Every workspace has its own history since the titles are part of its data
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputHistory {
+    pub projects: Vec<String>,
+    pub tasks: Vec<String>,
+}
+
+impl InputHistory {
+    // A missing or invalid history file results in an empty history
+    pub fn load() -> Self {
+        fs::read(Self::path())
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    // Load the history of the current workspace, add a value and save it again
+    pub fn update(kind: InputKind, value: String) -> io::Result<()> {
+        let mut history = Self::load();
+        history.add(kind, value);
+        fs::create_dir_all(workspace::directory(&workspace::current()))?;
+        fs::write(Self::path(), serde_json::to_vec_pretty(&history)?)
+    }
+
+    // Forget the history of the current workspace, e.g. because its data is encrypted now
+    pub fn remove() -> io::Result<()> {
+        match fs::remove_file(Self::path()) {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn entries(&self, kind: InputKind) -> &[String] {
+        match kind {
+            InputKind::Project => &self.projects,
+            InputKind::Task => &self.tasks,
+        }
+    }
+
+    // Add a value as the newest entry, an older copy of it is removed
+    fn add(&mut self, kind: InputKind, value: String) {
+        let entries = match kind {
+            InputKind::Project => &mut self.projects,
+            InputKind::Task => &mut self.tasks,
+        };
+        entries.retain(|entry| *entry != value);
+        entries.push(value);
+        if entries.len() > INPUT_HISTORY_LENGTH {
+            entries.drain(..entries.len() - INPUT_HISTORY_LENGTH);
+        }
+    }
+
+    fn path() -> PathBuf {
+        workspace::directory(&workspace::current()).join("inputs.json")
+    }
+}
+
+impl State {
+    // A missing or invalid state file results in the default state
+    pub fn load() -> Self {
+        fs::read(Self::path())
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        fs::create_dir_all(workspace::root_directory())?;
+        fs::write(Self::path(), serde_json::to_vec_pretty(self)?)
+    }
+
+    // Load the state, change it and save it again
+    pub fn update(change: impl FnOnce(&mut State)) -> io::Result<()> {
+        let mut state = Self::load();
+        change(&mut state);
+        state.save()
+    }
+
+    fn path() -> PathBuf {
+        workspace::root_directory().join("state.json")
+    }
+}
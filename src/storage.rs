@@ -0,0 +1,133 @@
+mod json;
+mod sqlite;
+
+use std::io;
+use std::path::Path;
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::application::TodoApp;
+use crate::crypto::Cipher;
+use crate::project::{Project, ProjectContainer};
+use crate::task::Task;
+
+pub use json::JsonStorage;
+pub use sqlite::SqliteStorage;
+
+// Place where projects are loaded from and saved to
+pub trait Storage {
+    // File that contains the data, it is watched for changes made by other programs
+    fn path(&self) -> &Path;
+    // Whether the data is encrypted at rest
+    fn encrypted(&self) -> bool {
+        false
+    }
+    // Load all projects
+    fn load(&mut self) -> io::Result<ProjectContainer>;
+    // Save all projects
+    // Implementations may only write what changed since the last load or save
+    fn save(&mut self, projects: &ProjectContainer) -> io::Result<()>;
+    // Find all tasks that match the query
+    fn tasks(&mut self, query: &Query) -> io::Result<Vec<TaskEntry>>;
+    // Count the tasks of every project
+    fn projects(&mut self) -> io::Result<Vec<ProjectEntry>>;
+}
+
+// Open the SQLite database if it exists and the JSON data file otherwise
+// The cipher is only used for the JSON data file
+pub fn open(cipher: Option<Cipher>) -> io::Result<Box<dyn Storage>> {
+    let database_path = TodoApp::database_path();
+    if database_path.exists() {
+        Ok(Box::new(SqliteStorage::open(&database_path)?))
+    } else {
+        Ok(Box::new(JsonStorage::new(TodoApp::data_path(), cipher)))
+    }
+}
+
+// Task as it is read by queries and written to the output of the command line
+// This is intentionally decoupled from `Task` so that the output stays stable
+// even if the layout of the data file changes
+#[derive(Serialize)]
+pub struct TaskEntry {
+    pub id: Uuid,
+    pub project: String,
+    pub title: String,
+    pub done: bool,
+    pub created_at: DateTime<Local>,
+    pub modified_at: DateTime<Local>,
+    pub content: String,
+}
+
+// Project as it is read by queries and written to the output of the command line
+#[derive(Serialize)]
+pub struct ProjectEntry {
+    pub id: Uuid,
+    pub title: String,
+    pub tasks: usize,
+    pub done_tasks: usize,
+}
+
+impl TaskEntry {
+    pub fn new(project: &Project, task: &Task) -> Self {
+        TaskEntry {
+            id: task.id,
+            project: project.title.clone(),
+            title: task.title.clone(),
+            done: task.done,
+            created_at: task.created_at,
+            modified_at: task.modified_at,
+            content: task.content.clone(),
+        }
+    }
+}
+
+impl ProjectEntry {
+    pub fn new(project: &Project) -> Self {
+        ProjectEntry {
+            id: project.id,
+            title: project.title.clone(),
+            tasks: project.tasks.len(),
+            done_tasks: project.tasks.iter().filter(|task| task.done).count(),
+        }
+    }
+}
+
+// Restricts which tasks are returned by `Storage::tasks`
+#[derive(Default)]
+pub struct Query {
+    pub project: Option<Uuid>,
+    pub done: Option<bool>,
+    // Tasks created on or after this date
+    pub since: Option<NaiveDate>,
+    // Tasks created on or before this date
+    pub until: Option<NaiveDate>,
+    // Every word has to be the beginning of a word in the title or content
+    pub search: Option<String>,
+}
+
+impl Query {
+    pub fn matches(&self, project: &Project, task: &Task) -> bool {
+        let created_at = task.created_at.date_naive();
+
+        self.project.is_none_or(|id| project.id == id)
+            && self.done.is_none_or(|done| task.done == done)
+            && self.since.is_none_or(|since| created_at >= since)
+            && self.until.is_none_or(|until| created_at <= until)
+            && self.search.as_ref().is_none_or(|search| {
+                let words = [search_words(&task.title), search_words(&task.content)].concat();
+                search_words(search)
+                    .iter()
+                    .all(|term| words.iter().any(|word| word.starts_with(term)))
+            })
+    }
+}
+
+// Split text into lowercase words the same way the SQLite full text search does
+fn search_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
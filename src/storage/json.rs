@@ -0,0 +1,122 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::crypto::{self, Cipher};
+use crate::project::ProjectContainer;
+use crate::storage::{ProjectEntry, Query, Storage, TaskEntry};
+
+// All projects are stored in a single JSON document that is rewritten on every save
+// The document is encrypted if a cipher is given
+pub struct JsonStorage {
+    path: PathBuf,
+    cipher: Option<Cipher>,
+}
+
+impl JsonStorage {
+    pub fn new(path: PathBuf, cipher: Option<Cipher>) -> Self {
+        JsonStorage { path, cipher }
+    }
+
+    // Read projects from the content of a data file
+    pub fn decode(&self, data: &[u8]) -> io::Result<ProjectContainer> {
+        if !crypto::is_encrypted(data) {
+            return Ok(serde_json::from_slice(data)?);
+        }
+
+        let cipher = self.cipher.as_ref().ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::PermissionDenied,
+                "The data file is encrypted, a passphrase is required",
+            )
+        })?;
+        let json = cipher
+            .decrypt(data)
+            .map_err(|error| io::Error::new(ErrorKind::InvalidData, error))?;
+
+        Ok(serde_json::from_slice(&json)?)
+    }
+
+    // Write projects to the content of a data file
+    pub fn encode(&self, projects: &ProjectContainer) -> io::Result<Vec<u8>> {
+        let json = serde_json::to_vec(projects)?;
+
+        Ok(match &self.cipher {
+            Some(cipher) => cipher.encrypt(&json),
+            None => json,
+        })
+    }
+}
+
+impl Storage for JsonStorage {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    fn load(&mut self) -> io::Result<ProjectContainer> {
+        let data = match fs::read(&self.path) {
+            Ok(data) => data,
+            // No data has been saved yet
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                return Ok(ProjectContainer::new(true));
+            }
+            Err(error) => return Err(error),
+        };
+        let mut projects = self.decode(&data)?;
+        // Loading never writes, e.g. commands that only read don't hold the lock
+        // The generated ids are stored with the next save
+        projects.assign_missing_ids(&self.path, &data);
+
+        Ok(projects)
+    }
+
+    // Write projects to the data file and create its parent directory if it does not exist
+    // The data is written to a temporary file first and renamed, so readers that watch the
+    // data file never see a partly written one
+    fn save(&mut self, projects: &ProjectContainer) -> io::Result<()> {
+        if let Some(data_directory) = self.path.parent() {
+            fs::create_dir_all(data_directory)?;
+        }
+
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(format!(".{}.tmp", std::process::id()));
+        let temporary = PathBuf::from(temporary);
+        if let Err(error) = fs::write(&temporary, self.encode(projects)?)
+            .and_then(|_| fs::rename(&temporary, &self.path))
+        {
+            let _ = fs::remove_file(&temporary);
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    fn tasks(&mut self, query: &Query) -> io::Result<Vec<TaskEntry>> {
+        let projects = self.load()?;
+        let mut tasks = Vec::new();
+        for project in projects.projects() {
+            for task in project
+                .tasks
+                .iter()
+                .filter(|task| query.matches(project, task))
+            {
+                tasks.push(TaskEntry::new(project, task));
+            }
+        }
+
+        Ok(tasks)
+    }
+
+    fn projects(&mut self) -> io::Result<Vec<ProjectEntry>> {
+        Ok(self
+            .load()?
+            .projects()
+            .iter()
+            .map(ProjectEntry::new)
+            .collect())
+    }
+}
@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone};
+use rusqlite::types::{Type, Value};
+use rusqlite::{Connection, Row, params, params_from_iter};
+use uuid::Uuid;
+
+use crate::project::{Project, ProjectContainer};
+use crate::storage::{ProjectEntry, Query, Storage, TaskEntry, search_words};
+use crate::task::Task;
+use crate::widgets::ContainerWidget;
+
+// Projects and tasks are stored in an SQLite database
+//
+// Only the rows that changed since the last load or save are written. Dates are stored
+// as microseconds since the unix epoch so that they can be compared in queries. The title
+// and content of tasks are indexed for full text search.
+
+const SCHEMA: &str = "
+PRAGMA foreign_keys = ON;
+
+CREATE TABLE IF NOT EXISTS projects (
+    id TEXT PRIMARY KEY,
+    position INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    done INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS tasks (
+    id TEXT PRIMARY KEY,
+    project_id TEXT NOT NULL REFERENCES projects (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    modified_at INTEGER NOT NULL,
+    done INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS tasks_by_project ON tasks (project_id, position);
+CREATE INDEX IF NOT EXISTS tasks_by_done ON tasks (done, created_at);
+CREATE INDEX IF NOT EXISTS tasks_by_created_at ON tasks (created_at);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS tasks_search USING fts5 (title, content, content = 'tasks');
+
+CREATE TRIGGER IF NOT EXISTS tasks_search_insert AFTER INSERT ON tasks BEGIN
+    INSERT INTO tasks_search (rowid, title, content) VALUES (new.rowid, new.title, new.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS tasks_search_delete AFTER DELETE ON tasks BEGIN
+    INSERT INTO tasks_search (tasks_search, rowid, title, content)
+    VALUES ('delete', old.rowid, old.title, old.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS tasks_search_update AFTER UPDATE ON tasks BEGIN
+    INSERT INTO tasks_search (tasks_search, rowid, title, content)
+    VALUES ('delete', old.rowid, old.title, old.content);
+    INSERT INTO tasks_search (rowid, title, content) VALUES (new.rowid, new.title, new.content);
+END;
+
+-- Selection of the project list, there is only a single row
+CREATE TABLE IF NOT EXISTS state (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    selected INTEGER NOT NULL,
+    focused INTEGER NOT NULL
+);
+";
+
+const TASK_COLUMNS: &str = "tasks.id, tasks.title, tasks.content, tasks.created_at, \
+                            tasks.modified_at, tasks.done";
+
+pub struct SqliteStorage {
+    path: PathBuf,
+    connection: Connection,
+    // Content of the database after the last load or save
+    // Everything is written if this is None
+    snapshot: Option<Snapshot>,
+}
+
+#[derive(PartialEq)]
+struct ProjectRow {
+    position: usize,
+    title: String,
+    done: bool,
+}
+
+#[derive(PartialEq)]
+struct TaskRow {
+    project_id: Uuid,
+    position: usize,
+    task: Task,
+}
+
+#[derive(Default)]
+struct Snapshot {
+    projects: HashMap<Uuid, ProjectRow>,
+    tasks: HashMap<Uuid, TaskRow>,
+    state: Option<(usize, bool)>,
+}
+
+impl Snapshot {
+    fn new(projects: &ProjectContainer) -> Self {
+        let mut snapshot = Snapshot {
+            state: Some((projects.selected(), projects.is_focused())),
+            ..Snapshot::default()
+        };
+        for (position, project) in projects.projects().iter().enumerate() {
+            snapshot.projects.insert(
+                project.id,
+                ProjectRow {
+                    position,
+                    title: project.title.clone(),
+                    done: project.done,
+                },
+            );
+            for (position, task) in project.tasks.iter().enumerate() {
+                snapshot.tasks.insert(
+                    task.id,
+                    TaskRow {
+                        project_id: project.id,
+                        position,
+                        task: task.clone(),
+                    },
+                );
+            }
+        }
+
+        snapshot
+    }
+}
+
+impl SqliteStorage {
+    // Open the database and create it if it does not exist
+    pub fn open(path: &Path) -> io::Result<Self> {
+        if let Some(data_directory) = path.parent() {
+            std::fs::create_dir_all(data_directory)?;
+        }
+        let connection = Connection::open(path).map_err(io::Error::other)?;
+        connection.execute_batch(SCHEMA).map_err(io::Error::other)?;
+
+        Ok(SqliteStorage {
+            path: path.to_path_buf(),
+            connection,
+            snapshot: None,
+        })
+    }
+
+    fn read_projects(&self) -> rusqlite::Result<ProjectContainer> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT id, title, done FROM projects ORDER BY position")?;
+        let mut projects = statement
+            .query_map([], |row| {
+                Ok(Project {
+                    id: read_id(row, 0)?,
+                    title: row.get(1)?,
+                    tasks: Vec::new(),
+                    done: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<Project>>>()?;
+        let indices: HashMap<Uuid, usize> = projects
+            .iter()
+            .enumerate()
+            .map(|(index, project)| (project.id, index))
+            .collect();
+
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT tasks.project_id, {} FROM tasks ORDER BY project_id, position",
+            TASK_COLUMNS
+        ))?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            if let Some(index) = indices.get(&read_id(row, 0)?) {
+                projects[*index].tasks.push(read_task(row, 1)?);
+            }
+        }
+
+        let (selected, focused) = self
+            .connection
+            .query_row("SELECT selected, focused FROM state", [], |row| {
+                Ok((row.get::<_, i64>(0)? as usize, row.get(1)?))
+            })
+            .unwrap_or((0, true));
+
+        Ok(ProjectContainer::with_projects(projects, selected, focused))
+    }
+
+    // Write everything that differs from the previous snapshot
+    fn write_changes(
+        &mut self,
+        previous: Option<&Snapshot>,
+        current: &Snapshot,
+    ) -> rusqlite::Result<()> {
+        let transaction = self.connection.transaction()?;
+        let empty = Snapshot::default();
+        let previous = match previous {
+            Some(previous) => previous,
+            None => {
+                transaction.execute_batch("DELETE FROM tasks; DELETE FROM projects;")?;
+                &empty
+            }
+        };
+
+        // Deleting a project also deletes its tasks
+        for id in previous.projects.keys() {
+            if !current.projects.contains_key(id) {
+                transaction.execute("DELETE FROM projects WHERE id = ?1", [id.to_string()])?;
+            }
+        }
+        for id in previous.tasks.keys() {
+            if !current.tasks.contains_key(id) {
+                transaction.execute("DELETE FROM tasks WHERE id = ?1", [id.to_string()])?;
+            }
+        }
+
+        // Projects are written first since tasks reference them
+        for (id, row) in &current.projects {
+            if previous.projects.get(id) != Some(row) {
+                transaction.execute(
+                    "INSERT INTO projects (id, position, title, done) VALUES (?1, ?2, ?3, ?4)
+                     ON CONFLICT (id) DO UPDATE SET
+                        position = excluded.position,
+                        title = excluded.title,
+                        done = excluded.done",
+                    params![id.to_string(), row.position as i64, row.title, row.done],
+                )?;
+            }
+        }
+        for (id, row) in &current.tasks {
+            if previous.tasks.get(id) != Some(row) {
+                let task = &row.task;
+                transaction.execute(
+                    "INSERT INTO tasks (id, project_id, position, title, content, created_at,
+                        modified_at, done)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                     ON CONFLICT (id) DO UPDATE SET
+                        project_id = excluded.project_id,
+                        position = excluded.position,
+                        title = excluded.title,
+                        content = excluded.content,
+                        created_at = excluded.created_at,
+                        modified_at = excluded.modified_at,
+                        done = excluded.done",
+                    params![
+                        id.to_string(),
+                        row.project_id.to_string(),
+                        row.position as i64,
+                        task.title,
+                        task.content,
+                        task.created_at.timestamp_micros(),
+                        task.modified_at.timestamp_micros(),
+                        task.done
+                    ],
+                )?;
+            }
+        }
+
+        if let Some((selected, focused)) = current.state
+            && previous.state != current.state
+        {
+            transaction.execute(
+                "INSERT OR REPLACE INTO state (id, selected, focused) VALUES (0, ?1, ?2)",
+                params![selected as i64, focused],
+            )?;
+        }
+
+        transaction.commit()
+    }
+
+    fn query_tasks(&self, query: &Query) -> rusqlite::Result<Vec<TaskEntry>> {
+        let mut sql = format!(
+            "SELECT projects.title, {} FROM tasks JOIN projects ON projects.id = tasks.project_id
+             WHERE 1",
+            TASK_COLUMNS
+        );
+        let mut values = Vec::new();
+        if let Some(project) = query.project {
+            sql.push_str(" AND tasks.project_id = ?");
+            values.push(Value::Text(project.to_string()));
+        }
+        if let Some(done) = query.done {
+            sql.push_str(" AND tasks.done = ?");
+            values.push(Value::Integer(done as i64));
+        }
+        if let Some(since) = query.since {
+            sql.push_str(" AND tasks.created_at >= ?");
+            values.push(Value::Integer(start_of_day(since)));
+        }
+        if let Some(until) = query.until.and_then(|until| until.succ_opt()) {
+            sql.push_str(" AND tasks.created_at < ?");
+            values.push(Value::Integer(start_of_day(until)));
+        }
+        if let Some(search) = &query.search {
+            // Match the beginning of every word
+            let terms: Vec<String> = search_words(search)
+                .iter()
+                .map(|word| format!("\"{}\"*", word))
+                .collect();
+            if !terms.is_empty() {
+                sql.push_str(
+                    " AND tasks.rowid IN (SELECT rowid FROM tasks_search WHERE tasks_search MATCH ?)",
+                );
+                values.push(Value::Text(terms.join(" ")));
+            }
+        }
+        sql.push_str(" ORDER BY projects.position, tasks.position");
+
+        let mut statement = self.connection.prepare(&sql)?;
+        statement
+            .query_map(params_from_iter(values), |row| {
+                let task = read_task(row, 1)?;
+                Ok(TaskEntry {
+                    id: task.id,
+                    project: row.get(0)?,
+                    title: task.title,
+                    done: task.done,
+                    created_at: task.created_at,
+                    modified_at: task.modified_at,
+                    content: task.content,
+                })
+            })?
+            .collect()
+    }
+
+    fn query_projects(&self) -> rusqlite::Result<Vec<ProjectEntry>> {
+        let mut statement = self.connection.prepare(
+            "SELECT projects.id, projects.title, COUNT(tasks.id), COALESCE(SUM(tasks.done), 0)
+             FROM projects LEFT JOIN tasks ON tasks.project_id = projects.id
+             GROUP BY projects.id ORDER BY projects.position",
+        )?;
+        statement
+            .query_map([], |row| {
+                Ok(ProjectEntry {
+                    id: read_id(row, 0)?,
+                    title: row.get(1)?,
+                    tasks: row.get::<_, i64>(2)? as usize,
+                    done_tasks: row.get::<_, i64>(3)? as usize,
+                })
+            })?
+            .collect()
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&mut self) -> io::Result<ProjectContainer> {
+        let projects = self.read_projects().map_err(io::Error::other)?;
+        self.snapshot = Some(Snapshot::new(&projects));
+
+        Ok(projects)
+    }
+
+    fn save(&mut self, projects: &ProjectContainer) -> io::Result<()> {
+        // Write everything on the next save if this one fails
+        let previous = self.snapshot.take();
+        let current = Snapshot::new(projects);
+        self.write_changes(previous.as_ref(), &current)
+            .map_err(io::Error::other)?;
+        self.snapshot = Some(current);
+
+        Ok(())
+    }
+
+    fn tasks(&mut self, query: &Query) -> io::Result<Vec<TaskEntry>> {
+        self.query_tasks(query).map_err(io::Error::other)
+    }
+
+    fn projects(&mut self) -> io::Result<Vec<ProjectEntry>> {
+        self.query_projects().map_err(io::Error::other)
+    }
+}
+
+fn read_id(row: &Row, index: usize) -> rusqlite::Result<Uuid> {
+    let id: String = row.get(index)?;
+    Uuid::parse_str(&id).map_err(|error| {
+        rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(error))
+    })
+}
+
+fn read_date(row: &Row, index: usize) -> rusqlite::Result<DateTime<Local>> {
+    let micros: i64 = row.get(index)?;
+    DateTime::from_timestamp_micros(micros)
+        .map(|date| date.with_timezone(&Local))
+        .ok_or(rusqlite::Error::IntegralValueOutOfRange(index, micros))
+}
+
+// Read the columns of `TASK_COLUMNS` starting at `offset`
+fn read_task(row: &Row, offset: usize) -> rusqlite::Result<Task> {
+    Ok(Task {
+        id: read_id(row, offset)?,
+        title: row.get(offset + 1)?,
+        content: row.get(offset + 2)?,
+        created_at: read_date(row, offset + 3)?,
+        modified_at: read_date(row, offset + 4)?,
+        done: row.get(offset + 5)?,
+    })
+}
+
+// Microseconds since the unix epoch at local midnight of the given date
+fn start_of_day(date: NaiveDate) -> i64 {
+    Local
+        .from_local_datetime(&date.and_time(NaiveTime::MIN))
+        .earliest()
+        .map_or(0, |date| date.timestamp_micros())
+}
@@ -1,18 +1,24 @@
 use chrono::{DateTime, Local};
 use crossterm::style::Stylize;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use crate::buffer::Buffer;
+use crate::layout::{self, Constraint, Direction};
 use crate::project::Project;
 use crate::utils::Rect;
 use crate::utils::border;
 use crate::utils::build_row;
-use crate::utils::split_rect_by_height;
+use crate::utils::truncate;
 use crate::widgets::ContainerWidget;
 use crate::widgets::Widget;
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Task {
+    // Data files written before ids were introduced don't contain an id
+    // A nil id is replaced by a generated one after loading
+    #[serde(default)]
+    pub id: Uuid,
     pub title: String,
     pub content: String,
     pub created_at: DateTime<Local>,
@@ -25,6 +31,21 @@ pub struct TaskContainer {
     tasks: Vec<Task>,
     selected: usize,
     focused: bool,
+    // Height of the task list in percent, the content gets the rest
+    list_height: u16,
+}
+
+impl Task {
+    pub fn new(title: String) -> Self {
+        Task {
+            id: Uuid::new_v4(),
+            title,
+            content: String::new(),
+            created_at: Local::now(),
+            modified_at: Local::now(),
+            done: false,
+        }
+    }
 }
 
 impl TaskContainer {
@@ -33,9 +54,23 @@ impl TaskContainer {
             tasks: Vec::new(),
             selected: 0,
             focused,
+            list_height: 50,
         }
     }
 
+    pub fn set_list_height(&mut self, percentage: u16) {
+        self.list_height = percentage.min(100);
+    }
+
+    // Split the area into the task list and the content of the selected task
+    pub fn split(&self, available_area: &Rect) -> [Rect; 2] {
+        layout::split(
+            &self.rect(available_area),
+            Direction::Vertical,
+            [Constraint::Percentage(self.list_height), Constraint::Min(0)],
+        )
+    }
+
     pub fn set_project(&mut self, project: Option<&mut Project>) {
         if self.tasks.len() < self.selected {
             self.selected = self.tasks.len().saturating_sub(1);
@@ -56,62 +91,39 @@ impl TaskContainer {
     pub fn len(&self) -> usize {
         self.tasks.len()
     }
-}
 
-impl ContainerWidget for TaskContainer {
-    fn move_up(&mut self) {
-        if !self.tasks.is_empty() {
-            if self.selected != 0 {
-                self.selected -= 1;
-            } else {
-                self.selected = self.tasks.len() - 1;
-            }
-        }
-    }
-
-    fn move_down(&mut self) {
-        if !self.tasks.is_empty() {
-            if self.selected != (self.tasks.len() - 1) {
-                self.selected += 1;
-            } else {
-                self.selected = 0;
-            }
-        }
-    }
+    // Whether the column belongs to the Done column of the task list
+    pub fn is_done_column(&self, available_area: &Rect, column: u16) -> bool {
+        let [area, _] = self.split(available_area);
+        let [title_column_space, done_column_space, _] = Self::columns(&area);
+        let start = area.x as usize + 1 + title_column_space;
 
-    fn is_focused(&self) -> bool {
-        self.focused
+        (start..start + done_column_space).contains(&(column as usize))
     }
 
-    fn set_focus(&mut self, focus: bool) {
-        self.focused = focus;
-    }
-}
+    // Width of the title, done and created at columns
+    // The columns on the right are cut off first if the list is too narrow
+    fn columns(task_list_area: &Rect) -> [usize; 3] {
+        let space = (task_list_area.width as usize).saturating_sub(2);
+        let title = space.saturating_sub(10 + 19).max(space.min(10));
+        let done = (space - title).min(10);
 
-impl Widget for TaskContainer {
-    fn rect(&self, available_area: &Rect) -> Rect {
-        available_area.clone()
+        [title, done, space - title - done]
     }
 
-    fn render(&self, buffer: &mut Buffer, available_area: &Rect) {
-        let mut selected_task_content = String::new();
-
-        // Calculate list and content areas
-        let (task_list_area, task_content_area) = split_rect_by_height(&self.rect(available_area));
+    fn render_list(&self, buffer: &mut Buffer, task_list_area: &Rect) {
         // Draw border for tasks
         border(
             buffer,
-            &task_list_area,
+            task_list_area,
             self.is_focused(),
             String::from("Tasks"),
             None,
             None,
         );
 
-        let done_column_space: usize = 10;
-        let created_at_column_space: usize = 19;
-        let title_column_space =
-            (task_list_area.width - 2) as usize - created_at_column_space - done_column_space;
+        let [title_column_space, done_column_space, created_at_column_space] =
+            Self::columns(task_list_area);
 
         // Draw header
         let header = build_row(vec![
@@ -119,17 +131,18 @@ impl Widget for TaskContainer {
             ("Done", done_column_space),
             ("Created At", created_at_column_space),
         ]);
-        buffer.write_string(task_list_area.x + 1, task_list_area.y + 1, header.bold());
+        // Without a row between the borders there is no space for the header
+        if task_list_area.height > 2 {
+            buffer.write_string(task_list_area.x + 1, task_list_area.y + 1, header.bold());
+        }
 
-        // Draw tasks
-        for (i, task) in self.tasks.iter().enumerate() {
+        // Draw as many tasks as fit between the header and the bottom border
+        let rows = (task_list_area.height as usize).saturating_sub(3);
+        for (i, task) in self.tasks.iter().enumerate().take(rows) {
             let mut styled_task = build_row(vec![
+                // Keep one column free to separate the title from the next column
                 (
-                    if task.title.len() > title_column_space - 1 {
-                        &task.title[..title_column_space - 1]
-                    } else {
-                        &task.title
-                    },
+                    truncate(&task.title, title_column_space.saturating_sub(1)),
                     title_column_space,
                 ),
                 (&task.done.to_string(), done_column_space),
@@ -146,7 +159,6 @@ impl Widget for TaskContainer {
             };
 
             if i == self.selected && self.focused {
-                selected_task_content = task.content.clone();
                 styled_task = styled_task.black().on_white();
             }
 
@@ -156,11 +168,18 @@ impl Widget for TaskContainer {
                 styled_task,
             );
         }
+    }
+
+    fn render_content(&self, buffer: &mut Buffer, task_content_area: &Rect) {
+        let selected_task_content = match self.tasks.get(self.selected) {
+            Some(task) if self.focused => task.content.as_str(),
+            _ => "",
+        };
 
         // Draw border for task content
         border(
             buffer,
-            &task_content_area,
+            task_content_area,
             false,
             String::from("Content"),
             None,
@@ -169,16 +188,81 @@ impl Widget for TaskContainer {
         // TODO: Don't truncate and make this not just a preview but a scrollable area
         for (i, line) in selected_task_content
             .lines()
-            .take(task_content_area.height as usize - 2)
+            .take((task_content_area.height as usize).saturating_sub(2))
             .enumerate()
         {
             buffer.write_string(
                 task_content_area.x + 1,
                 task_content_area.y + 1 + i as u16,
-                line[..line.len().min(task_content_area.width as usize - 2)]
+                truncate(line, (task_content_area.width as usize).saturating_sub(2))
                     .to_string()
                     .reset(),
             );
         }
     }
 }
+
+impl ContainerWidget for TaskContainer {
+    fn move_up(&mut self) {
+        if !self.tasks.is_empty() {
+            if self.selected != 0 {
+                self.selected -= 1;
+            } else {
+                self.selected = self.tasks.len() - 1;
+            }
+        }
+    }
+
+    fn move_down(&mut self) {
+        if !self.tasks.is_empty() {
+            if self.selected != (self.tasks.len() - 1) {
+                self.selected += 1;
+            } else {
+                self.selected = 0;
+            }
+        }
+    }
+
+    fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    fn set_focus(&mut self, focus: bool) {
+        self.focused = focus;
+    }
+
+    fn select(&mut self, index: usize) {
+        if index < self.tasks.len() {
+            self.selected = index;
+        }
+    }
+
+    fn item_at(&self, available_area: &Rect, column: u16, row: u16) -> Option<usize> {
+        let [area, _] = self.split(available_area);
+        // Tasks are listed below the header, one per row
+        if column <= area.x || column + 1 >= area.x + area.width || row < area.y + 2 {
+            return None;
+        }
+        let index = (row - area.y - 2) as usize;
+        let rows = (area.height as usize).saturating_sub(3);
+
+        (index < rows && index < self.tasks.len()).then_some(index)
+    }
+}
+
+impl Widget for TaskContainer {
+    fn rect(&self, available_area: &Rect) -> Rect {
+        available_area.clone()
+    }
+
+    fn render(&self, buffer: &mut Buffer, available_area: &Rect) {
+        let [task_list_area, task_content_area] = self.split(available_area);
+        // A collapsed pane is not drawn at all
+        if task_list_area.height >= 2 {
+            self.render_list(buffer, &task_list_area);
+        }
+        if task_content_area.height >= 2 {
+            self.render_content(buffer, &task_content_area);
+        }
+    }
+}
@@ -2,7 +2,7 @@ use crate::utils::Rect;
 use crossterm::{QueueableCommand, terminal};
 use crossterm::{
     cursor,
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
@@ -14,7 +14,13 @@ pub fn prepare_terminal() -> Result<(), Error> {
     enable_raw_mode()?;
     // Enable mouse support and make sure terminal starts in an alternate screen
     // Alternate means that the current screen is restored after exiting the application
-    execute!(stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    // Pasted text is reported as a whole instead of key by key
+    execute!(
+        stdout(),
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     stdout().queue(cursor::Hide)?;
 
     Ok(())
@@ -25,8 +31,13 @@ pub fn prepare_terminal() -> Result<(), Error> {
 pub fn restore_terminal() -> Result<(), Error> {
     disable_raw_mode()?;
 
-    execute!(stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
-    execute!(stdout(), cursor::Show)?;
+    execute!(
+        stdout(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )?;
+    execute!(stdout(), cursor::SetCursorStyle::DefaultUserShape, cursor::Show)?;
 
     Ok(())
 }
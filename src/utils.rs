@@ -1,5 +1,11 @@
 use crate::buffer::Buffer;
 use crossterm::style::{Color, StyledContent, Stylize};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+use uuid::Uuid;
+
+// Number of characters of an id that are displayed
+const SHORT_ID_LENGTH: usize = 8;
 
 // A Rect is a description of an area where:
 // * x and y are the coordinates for the top left corner
@@ -13,6 +19,16 @@ pub struct Rect {
     pub width: u16,
 }
 
+impl Rect {
+    // Whether the terminal cell is inside of the area
+    pub fn contains(&self, column: u16, row: u16) -> bool {
+        column >= self.x
+            && column < self.x + self.width
+            && row >= self.y
+            && row < self.y + self.height
+    }
+}
+
 // Draw border on a specific area
 // Widget can use this function to draw borders
 pub fn border(
@@ -26,11 +42,15 @@ pub fn border(
     footer: Option<StyledContent<String>>,
 ) {
     let line_color = if focused { Color::Yellow } else { Color::Reset };
-    let first_line = build_border_line('┌', '┐', '─', area.width);
-    let last_line = build_border_line('└', '┘', '─', area.width);
     // Add offset if header or footer is defined to make everything fit
     let top_offset = if header.is_some() { 1 } else { 0 };
     let bottom_offset = if footer.is_some() { 1 } else { 0 };
+    // A border needs at least two rows and two columns
+    if area.width < 2 || area.height < 2 + top_offset + bottom_offset {
+        return;
+    }
+    let first_line = build_border_line('┌', '┐', '─', area.width);
+    let last_line = build_border_line('└', '┘', '─', area.width);
 
     // Draw Header
     if let Some(header) = header {
@@ -39,11 +59,13 @@ pub fn border(
 
     // Draw first line with nice curves
     buffer.write_string(area.x, area.y + top_offset, first_line.with(line_color));
-    buffer.write_string(
-        area.x + ((area.width / 2) - title.len() as u16),
-        area.y + top_offset,
-        title.bold(),
-    );
+    // The title must not cover the corners
+    let title = truncate(&title, area.width as usize - 2).to_string();
+    let title_width = display_width(&title) as u16;
+    let title_x = (area.width / 2)
+        .saturating_sub(title_width)
+        .clamp(1, area.width - 1 - title_width);
+    buffer.write_string(area.x + title_x, area.y + top_offset, title.bold());
     // Draw vertical lines only on the left most and right most column
     for i in 1..area.height - top_offset - bottom_offset {
         buffer.write_string(
@@ -82,26 +104,92 @@ fn build_border_line(first: char, last: char, middle: char, length: u16) -> Stri
     line
 }
 
-pub fn split_rect_by_height(rect: &Rect) -> (Rect, Rect) {
-    let height = rect.height / 2;
-    let mut one = rect.clone();
-    let mut two = rect.clone();
-
-    one.height = height + 1;
-    two.y += one.height;
-    two.height = height - 1;
-
-    (one, two)
-}
-
 // Build a row that will be displayed in a container
+// Every content is cut off or padded to fill exactly its space of terminal columns
 pub fn build_row(contents: Vec<(&str, usize)>) -> StyledContent<String> {
     let mut row = String::new();
 
     for (content, space) in contents {
+        let content = truncate(content, space);
         row.push_str(content);
-        row.push_str(&" ".repeat(space - content.len()));
+        row.push_str(&" ".repeat(space - display_width(content)));
     }
 
     row.stylize()
 }
+
+// Number of terminal columns that are needed to display the text
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+// Longest beginning of the text that fits into `width` columns
+// Grapheme clusters (e.g. emoji or characters with accents) are never split
+pub fn truncate(text: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (index, grapheme) in text.grapheme_indices(true) {
+        used += grapheme.width();
+        if used > width {
+            return &text[..index];
+        }
+    }
+
+    text
+}
+
+// Break the text into lines that fit into `width` columns
+// Lines are broken between words, words that are longer than a line are broken anywhere
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && display_width(&line) + 1 + display_width(word) <= width {
+                line.push(' ');
+                line.push_str(word);
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            let mut rest = word;
+            while display_width(rest) > width {
+                let part = truncate(rest, width);
+                // Nothing fits if the first grapheme is wider than the line
+                if part.is_empty() {
+                    break;
+                }
+                lines.push(part.to_owned());
+                rest = &rest[part.len()..];
+            }
+            line.push_str(rest);
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+// Longest end of the text that fits into `width` columns
+pub fn truncate_start(text: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (index, grapheme) in text.grapheme_indices(true).rev() {
+        used += grapheme.width();
+        if used > width {
+            return &text[index + grapheme.len()..];
+        }
+    }
+
+    text
+}
+
+// Shortened representation of an id that is used when displaying it to the user
+pub fn short_id(id: &Uuid) -> String {
+    id.simple().to_string()[..SHORT_ID_LENGTH].to_string()
+}
+
+// Check whether a (short) id that was typed by the user refers to the given id
+pub fn id_matches(id: &Uuid, prefix: &str) -> bool {
+    let prefix = prefix.replace('-', "").to_lowercase();
+    !prefix.is_empty() && id.simple().to_string().starts_with(&prefix)
+}
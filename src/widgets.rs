@@ -1,4 +1,5 @@
 pub mod line_input;
+pub mod list_popup;
 pub mod message_box;
 
 use crate::{buffer::Buffer, utils::Rect};
@@ -23,6 +24,10 @@ pub trait ContainerWidget {
     fn is_focused(&self) -> bool;
     // Declare that this widget is focused, this is only used for styling puposes
     fn set_focus(&mut self, focus: bool);
+    // Select the item with the given index
+    fn select(&mut self, index: usize);
+    // Index of the item that is displayed at the given terminal cell
+    fn item_at(&self, available_area: &Rect, column: u16, row: u16) -> Option<usize>;
 }
 
 // A PopupWidget is basically a message box, similar to QMessageBox from QT
@@ -1,66 +1,257 @@
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use crossterm::style::Stylize;
+use unicode_segmentation::UnicodeSegmentation;
+use zeroize::{Zeroize, Zeroizing};
 
 use crate::buffer::Buffer;
-use crate::utils::border;
+use crate::layout::{self, Constraint};
+use crate::utils::{border, display_width, truncate, truncate_start};
 use crate::widgets::PopupWidget;
 use crate::widgets::Rect;
 use crate::widgets::Widget;
 
 pub struct LineInput {
-    input: String,
+    // The input is wiped from memory when it is replaced or closed since it can be a passphrase
+    input: Zeroizing<String>,
+    // Byte index in the input where text is inserted, it is always on a grapheme boundary
+    cursor: usize,
     visible: bool,
+    title: String,
+    // Whether the input is hidden, e.g. for passphrases
+    masked: bool,
+    // Values that can be recalled with Up and Down, the newest last
+    history: Vec<String>,
+    // Entry of the history that is shown, None while the user's own input is shown
+    history_index: Option<usize>,
+    // The user's own input while the history is browsed
+    draft: String,
+    // Values that the text before the cursor can be completed to with Tab
+    completions: Vec<String>,
 }
 
 impl LineInput {
     pub fn new() -> Self {
         LineInput {
-            input: String::new(),
+            input: Zeroizing::default(),
+            cursor: 0,
             visible: false,
+            title: String::from("Insert value here"),
+            masked: false,
+            history: Vec::new(),
+            history_index: None,
+            draft: String::new(),
+            completions: Vec::new(),
+        }
+    }
+
+    // Input that only shows a '*' for every character
+    // It is visible right away since it is not opened by a key press
+    pub fn masked(title: &str) -> Self {
+        LineInput {
+            input: Zeroizing::default(),
+            cursor: 0,
+            visible: true,
+            title: title.to_owned(),
+            masked: true,
+            history: Vec::new(),
+            history_index: None,
+            draft: String::new(),
+            completions: Vec::new(),
         }
     }
 
     pub fn value(&self) -> String {
-        self.input.to_owned()
+        self.input.to_string()
     }
 
+    // Remove the input without copying it, e.g. for passphrases
+    pub fn take_value(&mut self) -> Zeroizing<String> {
+        self.cursor = 0;
+        std::mem::take(&mut self.input)
+    }
+
+    // Replace the input, the cursor is placed at the end
     pub fn set_value(&mut self, input: String) {
-        self.input = input;
+        self.cursor = input.len();
+        self.input = Zeroizing::new(input);
+    }
+
+    // History and completions are only kept until the input is closed
+    pub fn set_history(&mut self, history: Vec<String>) {
+        self.history = history;
+        self.history_index = None;
+    }
+
+    pub fn set_completions(&mut self, completions: Vec<String>) {
+        self.completions = completions;
+    }
+
+    // Insert pasted text at the cursor
+    // Line breaks and other control characters can't be part of a single line
+    pub fn paste(&mut self, text: &str) {
+        let text: String = text
+            .chars()
+            .map(|char| if char.is_control() { ' ' } else { char })
+            .collect();
+        self.reserve(text.len());
+        self.input.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    fn insert(&mut self, char: char) {
+        self.reserve(char.len_utf8());
+        self.input.insert(self.cursor, char);
+        self.cursor += char.len_utf8();
+    }
+
+    // Make room for more text without leaving a copy of the input behind in freed memory
+    fn reserve(&mut self, additional: usize) {
+        if self.input.capacity() - self.input.len() < additional {
+            let capacity = (self.input.len() + additional).max(self.input.capacity() * 2);
+            let mut input = String::with_capacity(capacity);
+            input.push_str(&self.input);
+            self.input = Zeroizing::new(input);
+        }
+    }
+
+    // Remove everything between the position and the cursor
+    fn delete_to(&mut self, position: usize) {
+        let range = position.min(self.cursor)..position.max(self.cursor);
+        self.cursor = range.start;
+        self.input.replace_range(range, "");
+    }
+
+    // Show an older or newer entry of the history
+    // Going past the newest entry shows the user's own input again
+    fn recall(&mut self, older: bool) {
+        let index = match self.history_index {
+            None if older && !self.history.is_empty() => {
+                self.draft = self.input.to_string();
+                Some(self.history.len() - 1)
+            }
+            Some(index) if older => Some(index.saturating_sub(1)),
+            Some(index) if index + 1 < self.history.len() => Some(index + 1),
+            Some(_) => None,
+            None => return,
+        };
+        self.history_index = index;
+        let value = match index {
+            Some(index) => self.history[index].clone(),
+            None => std::mem::take(&mut self.draft),
+        };
+        self.set_value(value);
+    }
+
+    // Complete the text before the cursor as far as all matching completions agree
+    // The longest text that starts at a word and matches a completion is completed, so
+    // completions can contain spaces. A text with a single match is followed by a space
+    fn complete(&mut self) {
+        let before = &self.input[..self.cursor];
+        let starts = before
+            .char_indices()
+            .filter(|(_, char)| char.is_whitespace())
+            .map(|(index, char)| index + char.len_utf8());
+        let Some((word, matches)) = std::iter::once(0)
+            .chain(starts)
+            .map(|start| &before[start..])
+            .filter(|word| !word.is_empty())
+            .map(|word| {
+                let matches: Vec<&str> = self
+                    .completions
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|completion| completion.starts_with(word))
+                    .collect();
+                (word, matches)
+            })
+            .find(|(_, matches)| !matches.is_empty())
+        else {
+            return;
+        };
+        let completion = match matches.as_slice() {
+            [] => return,
+            [completion] => format!("{} ", completion),
+            [first, rest @ ..] => {
+                let mut prefix = *first;
+                for completion in rest {
+                    while !completion.starts_with(prefix) {
+                        prefix = &prefix[..prefix.char_indices().next_back().map_or(0, |(i, _)| i)];
+                    }
+                }
+                prefix.to_owned()
+            }
+        };
+        self.paste(&completion[word.len()..]);
+    }
+
+    // Start of the grapheme before the cursor
+    fn previous_grapheme(&self) -> usize {
+        self.input[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(index, _)| index)
+    }
+
+    // End of the grapheme after the cursor
+    fn next_grapheme(&self) -> usize {
+        self.input[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map_or(self.cursor, |grapheme| self.cursor + grapheme.len())
+    }
+
+    // Start of the word before the cursor, words are separated by whitespace
+    fn previous_word(&self) -> usize {
+        self.input[..self.cursor]
+            .trim_end()
+            .char_indices()
+            .rev()
+            .find(|(_, char)| char.is_whitespace())
+            .map_or(0, |(index, char)| index + char.len_utf8())
+    }
+
+    // End of the word after the cursor
+    fn next_word(&self) -> usize {
+        let after = &self.input[self.cursor..];
+        let start = after.len() - after.trim_start().len();
+        let end = after[start..]
+            .find(char::is_whitespace)
+            .map_or(after.len(), |index| start + index);
+
+        self.cursor + end
     }
 }
 
 impl Widget for LineInput {
     fn render(&self, buffer: &mut Buffer, available_area: &Rect) {
         let area = self.rect(available_area);
-        border(
-            buffer,
-            &area,
-            true,
-            String::from("Insert value here"),
-            None,
-            None,
-        );
-        // TODO: Make this more user friendly by adding a blinking cursor
-        // https://docs.rs/crossterm/latest/crossterm/cursor/enum.SetCursorStyle.html#variant.BlinkingBar
-        buffer.write_string(
-            area.x + 1,
-            area.y + area.height - 2,
-            self.input[self.input.len().saturating_sub(area.width as usize - 2)..]
-                .to_string()
-                .reset(),
-        );
+        border(buffer, &area, true, self.title.clone(), None, None);
+        // Nothing fits inside the border
+        if area.width < 3 || area.height < 3 {
+            return;
+        }
+        let (before, after) = self.input.split_at(self.cursor);
+        let (before, after) = if self.masked {
+            (
+                "*".repeat(before.graphemes(true).count()),
+                "*".repeat(after.graphemes(true).count()),
+            )
+        } else {
+            (before.to_owned(), after.to_owned())
+        };
+        // Scroll horizontally so that the cursor stays visible, one column is kept for it
+        let width = area.width as usize - 2;
+        let before = truncate_start(&before, width - 1);
+        let after = truncate(&after, width - display_width(before));
+        let x = area.x + 1;
+        let y = area.y + area.height - 2;
+        buffer.write_string(x, y, format!("{}{}", before, after).reset());
+        buffer.set_cursor(x + display_width(before) as u16, y);
     }
 
     fn rect(&self, available_rect: &Rect) -> Rect {
-        // Width and height define max input characters
-        // 120 means that we can insert 120 characters
-
-        Rect {
-            x: available_rect.width / 4,
-            y: available_rect.height / 4,
-            width: 120,
-            height: 3,
-        }
+        // Longer input is scrolled horizontally
+        layout::centered(available_rect, Constraint::Max(120), Constraint::Fixed(3))
     }
 }
 
@@ -69,24 +260,38 @@ impl PopupWidget for LineInput {
         // visible was introduced to be able to differentiate between the
         // line_input being displayed and user input after displaying the line_input
         if self.visible {
+            // Shortcuts are the same as in readline, characters that are typed with exactly
+            // both modifiers (e.g. AltGr on some systems) are inserted
+            let modifiers = key_event.modifiers;
+            let alt_gr = modifiers == KeyModifiers::CONTROL | KeyModifiers::ALT;
+            let control = !alt_gr && modifiers.contains(KeyModifiers::CONTROL);
+            let alt = !alt_gr && modifiers.contains(KeyModifiers::ALT);
             match key_event.code {
-                KeyCode::Char(char) => self.input.push(char),
-                KeyCode::Backspace => drop(self.input.pop()),
-                KeyCode::Esc => {
-                    self.visible = false;
-                    self.input.clear();
-                }
+                KeyCode::Char('a') if control => self.cursor = 0,
+                KeyCode::Char('e') if control => self.cursor = self.input.len(),
+                KeyCode::Char('w') if control => self.delete_to(self.previous_word()),
+                KeyCode::Char('u') if control => self.delete_to(0),
+                KeyCode::Char('k') if control => self.delete_to(self.input.len()),
+                KeyCode::Char('b') if alt => self.cursor = self.previous_word(),
+                KeyCode::Char('f') if alt => self.cursor = self.next_word(),
+                KeyCode::Char('d') if alt => self.delete_to(self.next_word()),
+                KeyCode::Char(_) if control || alt => {}
+                KeyCode::Char(char) => self.insert(char),
+                KeyCode::Left if control => self.cursor = self.previous_word(),
+                KeyCode::Right if control => self.cursor = self.next_word(),
+                KeyCode::Left => self.cursor = self.previous_grapheme(),
+                KeyCode::Right => self.cursor = self.next_grapheme(),
+                KeyCode::Up => self.recall(true),
+                KeyCode::Down => self.recall(false),
+                KeyCode::Tab => self.complete(),
+                KeyCode::Home => self.cursor = 0,
+                KeyCode::End => self.cursor = self.input.len(),
+                KeyCode::Backspace if alt => self.delete_to(self.previous_word()),
+                KeyCode::Backspace => self.delete_to(self.previous_grapheme()),
+                KeyCode::Delete => self.delete_to(self.next_grapheme()),
+                KeyCode::Esc => self.close(),
                 _ => {}
             }
-            // key codes but with modifiers
-            if let KeyEvent {
-                code: KeyCode::Char('w'),
-                modifiers: KeyModifiers::CONTROL,
-                ..
-            } = key_event
-            {
-                self.input.clear();
-            }
         } else {
             self.visible = true;
         }
@@ -94,6 +299,39 @@ impl PopupWidget for LineInput {
 
     fn close(&mut self) {
         self.visible = false;
-        self.input.clear();
+        self.input.zeroize();
+        self.cursor = 0;
+        self.history.clear();
+        self.history_index = None;
+        self.draft.clear();
+        self.completions.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_keys(keys: &[(KeyCode, KeyModifiers)]) -> String {
+        let mut input = LineInput::masked("Test");
+        for (code, modifiers) in keys {
+            input.process_input(&KeyEvent::new(*code, *modifiers));
+        }
+        input.value()
+    }
+
+    #[test]
+    fn only_alt_gr_characters_are_inserted_with_modifiers() {
+        let alt_gr = KeyModifiers::CONTROL | KeyModifiers::ALT;
+        let control_shift = KeyModifiers::CONTROL | KeyModifiers::SHIFT;
+        assert_eq!(
+            type_keys(&[
+                (KeyCode::Char('a'), KeyModifiers::NONE),
+                (KeyCode::Char('A'), control_shift),
+                (KeyCode::Char('B'), KeyModifiers::ALT | KeyModifiers::SHIFT),
+                (KeyCode::Char('@'), alt_gr),
+            ]),
+            "a@"
+        );
     }
 }
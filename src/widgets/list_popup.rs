@@ -0,0 +1,92 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::style::Stylize;
+
+use crate::buffer::Buffer;
+use crate::layout::{self, Constraint};
+use crate::utils::{border, build_row};
+use crate::widgets::PopupWidget;
+use crate::widgets::Rect;
+use crate::widgets::Widget;
+
+// Popup that lets the user select one entry of a list
+pub struct ListPopup {
+    title: String,
+    items: Vec<String>,
+    selected: usize,
+    visible: bool,
+}
+
+impl ListPopup {
+    pub fn new() -> Self {
+        ListPopup {
+            title: String::new(),
+            items: Vec::new(),
+            selected: 0,
+            visible: false,
+        }
+    }
+
+    pub fn set_items(&mut self, title: &str, items: Vec<String>) {
+        self.title = title.to_owned();
+        self.items = items;
+        self.selected = 0;
+    }
+
+    // Return the index of the selected item or None if the list is empty
+    pub fn selected(&self) -> Option<usize> {
+        (self.selected < self.items.len()).then_some(self.selected)
+    }
+}
+
+impl Widget for ListPopup {
+    fn render(&self, buffer: &mut Buffer, available_area: &Rect) {
+        let area = self.rect(available_area);
+        border(buffer, &area, true, self.title.clone(), None, None);
+
+        // Nothing fits inside the border
+        if area.width < 3 || area.height < 3 {
+            return;
+        }
+        let width = area.width as usize - 2;
+        let rows = area.height as usize - 2;
+        // Scroll so that the selected item is always visible
+        let first = self.selected.saturating_sub(rows - 1);
+        for (i, item) in self.items.iter().enumerate().skip(first).take(rows) {
+            let mut row = build_row(vec![(item, width)]).reset();
+            if i == self.selected {
+                row = row.black().on_white();
+            }
+            buffer.write_string(area.x + 1, area.y + 1 + (i - first) as u16, row);
+        }
+    }
+
+    fn rect(&self, available_rect: &Rect) -> Rect {
+        layout::centered(
+            available_rect,
+            Constraint::Percentage(50),
+            Constraint::Percentage(50),
+        )
+    }
+}
+
+impl PopupWidget for ListPopup {
+    fn process_input(&mut self, key_event: &KeyEvent) {
+        // visible was introduced to be able to differentiate between the
+        // list being displayed and user input after displaying the list
+        if self.visible {
+            match key_event.code {
+                KeyCode::Up if self.selected > 0 => self.selected -= 1,
+                KeyCode::Down if self.selected + 1 < self.items.len() => self.selected += 1,
+                _ => {}
+            }
+        } else {
+            self.visible = true;
+        }
+    }
+
+    fn close(&mut self) {
+        self.visible = false;
+        self.items.clear();
+        self.selected = 0;
+    }
+}
@@ -1,22 +1,27 @@
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use crossterm::style::{ContentStyle, StyledContent, Stylize};
 
 use crate::buffer::Buffer;
+use crate::layout::{self, Constraint};
 use crate::utils::border;
+use crate::utils::{display_width, truncate, wrap};
 use crate::widgets::PopupWidget;
 use crate::widgets::Rect;
 use crate::widgets::Widget;
 
-#[derive(PartialEq)]
-enum Button {
-    Ok,
-    No,
-}
+// Buttons that are displayed if no other buttons were set
+const DEFAULT_BUTTONS: [&str; 2] = ["Yes", "No"];
+// "No" is selected by default
+const DEFAULT_SELECTED: usize = 1;
+// Longer questions are wrapped into multiple lines
+const MAX_WIDTH: u16 = 100;
 
 pub struct MessageBox {
     question: String,
     visible: bool,
-    selected: Button,
+    buttons: Vec<&'static str>,
+    // Index of the selected button
+    selected: usize,
 }
 
 impl MessageBox {
@@ -24,18 +29,83 @@ impl MessageBox {
         MessageBox {
             question: String::new(),
             visible: false,
-            selected: Button::No,
+            buttons: DEFAULT_BUTTONS.to_vec(),
+            selected: DEFAULT_SELECTED,
         }
     }
 
+    // Whether the first button (e.g. "Yes") was selected
     pub fn accepted(&self) -> bool {
-        self.selected == Button::Ok
+        self.selected == 0
+    }
+
+    // Return the index of the selected button
+    pub fn selected(&self) -> usize {
+        self.selected
     }
 
     pub fn set_question(&mut self, text: &str) {
         self.question = text.to_owned();
     }
 
+    // Replace the Yes / No buttons until the message box is closed
+    pub fn set_buttons(&mut self, buttons: &[&'static str], selected: usize) {
+        self.buttons = buttons.to_vec();
+        self.selected = selected;
+    }
+
+    // Select a button, e.g. to make another button than "No" the default
+    pub fn select(&mut self, index: usize) {
+        if index < self.buttons.len() {
+            self.selected = index;
+        }
+    }
+
+    // Index of the button that belongs to a shortcut key
+    // The shortcut of a button is the first letter of its label, e.g. y for "Yes"
+    pub fn shortcut(&self, key_event: &KeyEvent) -> Option<usize> {
+        let KeyCode::Char(char) = key_event.code else {
+            return None;
+        };
+        if key_event
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            return None;
+        }
+
+        self.buttons.iter().position(|label| {
+            label
+                .chars()
+                .next()
+                .is_some_and(|first| first.eq_ignore_ascii_case(&char))
+        })
+    }
+
+    // Index of the button that is displayed at the given terminal cell
+    pub fn button_at(&self, available_area: &Rect, column: u16, row: u16) -> Option<usize> {
+        let area = self.rect(available_area);
+        if area.width < 3 || area.height < 3 || row != area.y + area.height - 2 {
+            return None;
+        }
+        // The whole slot of a button can be clicked, not only the label
+        let slot_width = area.width / self.buttons.len().max(1) as u16;
+        let index = (column.checked_sub(area.x)? / slot_width.max(1)) as usize;
+
+        (index < self.buttons.len()).then_some(index)
+    }
+
+    // Show the message box without waiting for the key that opened it
+    // This is used if the message box is opened by something other than a key press
+    pub fn show(&mut self) {
+        self.visible = true;
+    }
+
+    // Columns that are available for the question, one column is kept free next to the border
+    fn line_width(width: u16) -> usize {
+        (width as usize).saturating_sub(4)
+    }
+
     fn style_button(label: String, selected: bool) -> StyledContent<String> {
         if selected {
             StyledContent::new(ContentStyle::new().black().on_white(), label)
@@ -50,34 +120,45 @@ impl Widget for MessageBox {
         let area = self.rect(available_area);
         // Draw border
         border(buffer, &area, true, String::new(), None, None);
-        // Draw message box
-        buffer.write_string(
-            area.x + (area.width / 2) - (self.question.len() as u16 / 2),
-            area.y + area.height / 3,
-            self.question.clone().yellow(),
-        );
-        buffer.write_string(
-            area.x + 10,
-            area.y + (area.height * 80 / 100),
-            Self::style_button(String::from("Yes"), self.selected == Button::Ok),
-        );
-        buffer.write_string(
-            area.x + area.width - 10,
-            area.y + (area.height * 80 / 100),
-            Self::style_button(String::from("No"), self.selected == Button::No),
-        );
+        // Nothing fits inside the border
+        if area.width < 3 || area.height < 3 {
+            return;
+        }
+        // Draw the question centred line by line, one empty line above and below
+        let lines = wrap(&self.question, Self::line_width(area.width));
+        for (i, line) in lines
+            .iter()
+            .enumerate()
+            .take((area.height as usize).saturating_sub(4))
+        {
+            let line = truncate(line, area.width as usize - 2).to_string();
+            buffer.write_string(
+                area.x + (area.width - display_width(&line) as u16) / 2,
+                area.y + 2 + i as u16,
+                line.yellow(),
+            );
+        }
+        // Distribute the buttons evenly
+        let slot_width = area.width / self.buttons.len().max(1) as u16;
+        for (i, label) in self.buttons.iter().enumerate() {
+            let label = truncate(label, slot_width as usize).to_string();
+            buffer.write_string(
+                area.x + slot_width * i as u16 + (slot_width - display_width(&label) as u16) / 2,
+                area.y + area.height - 2,
+                Self::style_button(label, self.selected == i),
+            );
+        }
     }
 
     fn rect(&self, available_rect: &Rect) -> Rect {
-        // Width and height define max input characters
-        // 120 means that we can insert 120 characters
-
-        Rect {
-            x: available_rect.width / 4,
-            y: available_rect.height / 4,
-            width: 100,
-            height: 7,
-        }
+        // The box grows with the number of lines of the question
+        let width = available_rect.width.min(MAX_WIDTH);
+        let lines = wrap(&self.question, Self::line_width(width)).len().max(1) as u16;
+        layout::centered(
+            available_rect,
+            Constraint::Fixed(width),
+            Constraint::Fixed(lines + 6),
+        )
     }
 }
 
@@ -86,12 +167,13 @@ impl PopupWidget for MessageBox {
         // visible was introduced to be able to differentiate between the
         // line_input being displayed and user input after displaying the line_input
         if self.visible {
-            if key_event.code == KeyCode::Tab {
-                self.selected = if self.selected == Button::Ok {
-                    Button::No
-                } else {
-                    Button::Ok
-                };
+            let count = self.buttons.len();
+            match key_event.code {
+                KeyCode::Tab | KeyCode::Right => self.selected = (self.selected + 1) % count,
+                KeyCode::BackTab | KeyCode::Left => {
+                    self.selected = (self.selected + count - 1) % count
+                }
+                _ => {}
             }
         } else {
             self.visible = true;
@@ -100,7 +182,8 @@ impl PopupWidget for MessageBox {
 
     fn close(&mut self) {
         self.visible = false;
-        self.selected = Button::No;
+        self.buttons = DEFAULT_BUTTONS.to_vec();
+        self.selected = DEFAULT_SELECTED;
         self.question = String::new();
     }
 }
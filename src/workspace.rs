@@ -0,0 +1,96 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::state::State;
+
+// Named workspaces, every workspace has its own data directory
+//
+// The default workspace uses the data directory itself so existing data keeps working.
+// All other workspaces live in `workspaces/<name>` inside of it. A workspace is created
+// as soon as its data is saved for the first time.
+
+pub const DEFAULT: &str = "default";
+
+// Workspace that is used by this process, None until it was selected or looked up
+static CURRENT: Mutex<Option<String>> = Mutex::new(None);
+
+// Directory that contains the data of the default workspace and all other workspaces
+pub fn root_directory() -> PathBuf {
+    let home_path = env::var_os("HOME").unwrap();
+    Path::new(&home_path).join(".weeklyplaner")
+}
+
+// Data directory of a workspace
+pub fn directory(name: &str) -> PathBuf {
+    if name == DEFAULT {
+        root_directory()
+    } else {
+        root_directory().join("workspaces").join(name)
+    }
+}
+
+// Name of the workspace that is used, the last used one if none was selected
+pub fn current() -> String {
+    let mut current = CURRENT.lock().expect("Workspace lock is poisoned");
+    current.get_or_insert_with(last_used).clone()
+}
+
+// Use another workspace for this process
+pub fn select(name: &str) -> Result<(), String> {
+    validate(name)?;
+    *CURRENT.lock().expect("Workspace lock is poisoned") = Some(name.to_owned());
+
+    Ok(())
+}
+
+// Open this workspace the next time no workspace is selected
+pub fn remember(name: &str) -> Result<(), String> {
+    State::update(|state| state.workspace = Some(name.to_owned()))
+        .map_err(|error| format!("Could not save state: {}", error))
+}
+
+// All existing workspaces sorted by name, the default workspace always comes first
+pub fn list() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(root_directory().join("workspaces"))
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| validate(name).is_ok())
+                // `workspaces/default` is never used, the default workspace is the root
+                .filter(|name| name != DEFAULT)
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names.insert(0, String::from(DEFAULT));
+
+    names
+}
+
+// Names are used as directory names and have to be usable on the command line
+pub fn validate(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid workspace name \"{}\", only letters, digits, - and _ are allowed",
+            name
+        ));
+    }
+
+    Ok(())
+}
+
+// The last used workspace if it still exists
+fn last_used() -> String {
+    State::load()
+        .workspace
+        .filter(|name| validate(name).is_ok() && directory(name).is_dir())
+        .unwrap_or_else(|| String::from(DEFAULT))
+}
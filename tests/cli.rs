@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+// Home directory that is only used by one test
+fn home(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("wetopla-test-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(&path).unwrap();
+    path
+}
+
+fn run(home: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_wetopla"))
+        .args(args)
+        .env("HOME", home)
+        .env_remove("WETOPLA_PASSPHRASE")
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn add_rejects_an_empty_project_name() {
+    let home = home("add-empty-project");
+
+    for name in ["", "  "] {
+        let output = run(&home, &["add", name, "Some task"]);
+        assert!(!output.status.success());
+        assert!(stderr(&output).contains("Project name cannot be empty"));
+    }
+
+    // The task must not end up in another project either
+    assert!(run(&home, &["add", "Inbox", "First task"]).status.success());
+    assert!(!run(&home, &["add", "", "Second task"]).status.success());
+    let list = stdout(&run(&home, &["list"]));
+    assert!(list.contains("First task"));
+    assert!(!list.contains("Second task"));
+
+    std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[test]
+fn import_rejects_an_empty_project_name() {
+    let home = home("import-empty-project");
+    let file = home.join("tasks.csv");
+    std::fs::write(&file, "title\nImported task\n").unwrap();
+    let file = file.to_str().unwrap();
+
+    assert!(run(&home, &["add", "Inbox", "First task"]).status.success());
+    for name in ["", "  "] {
+        let output = run(&home, &["import", "csv", file, "--project", name]);
+        assert!(!output.status.success());
+        assert!(stderr(&output).contains("Project name cannot be empty"));
+    }
+    assert!(!stdout(&run(&home, &["list"])).contains("Imported task"));
+
+    std::fs::remove_dir_all(&home).unwrap();
+}